@@ -0,0 +1,370 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+use futures01::future::{self, Future};
+use lru::LruCache;
+
+use core_runtime::{DataCategory, Database, DatabaseError, DbIterator, FutRuntimeResult};
+
+/// Per-category LRU cache capacities, in number of entries.
+#[derive(Debug, Clone)]
+pub struct CacheConfig {
+    pub block:            usize,
+    pub transaction:      usize,
+    pub receipt:          usize,
+    pub state:            usize,
+    pub transaction_pool: usize,
+    pub journal:          usize,
+}
+
+impl Default for CacheConfig {
+    fn default() -> Self {
+        CacheConfig {
+            block:            1024,
+            transaction:      1024,
+            receipt:          1024,
+            state:            65536,
+            transaction_pool: 1024,
+            journal:          1024,
+        }
+    }
+}
+
+type Caches = HashMap<DataCategory, Mutex<LruCache<Vec<u8>, Vec<u8>>>>;
+
+/// Wraps a `Database` with an independent, size-bounded LRU cache per
+/// `DataCategory`. `get`/`get_batch`/`contains` consult the cache first and
+/// populate it on miss; `insert`/`insert_batch`/`remove`/`remove_batch`
+/// write through to the inner database and keep the cache consistent.
+/// Hot consensus paths (latest block/proof, frequently touched trie nodes)
+/// re-read the same keys repeatedly, and RocksDB point-lookups dominate
+/// their cost, so this sits in front of `block_db` and `state_db`.
+pub struct CachedDatabase<D> {
+    inner:  Arc<D>,
+    caches: Arc<Caches>,
+}
+
+impl<D: Database> CachedDatabase<D> {
+    pub fn new(inner: D, config: CacheConfig) -> Self {
+        let mut caches = HashMap::new();
+        caches.insert(DataCategory::Block, Mutex::new(LruCache::new(config.block)));
+        caches.insert(DataCategory::Transaction, Mutex::new(LruCache::new(config.transaction)));
+        caches.insert(DataCategory::Receipt, Mutex::new(LruCache::new(config.receipt)));
+        caches.insert(DataCategory::State, Mutex::new(LruCache::new(config.state)));
+        caches.insert(
+            DataCategory::TransactionPool,
+            Mutex::new(LruCache::new(config.transaction_pool)),
+        );
+        caches.insert(DataCategory::Journal, Mutex::new(LruCache::new(config.journal)));
+
+        CachedDatabase {
+            inner:  Arc::new(inner),
+            caches: Arc::new(caches),
+        }
+    }
+}
+
+fn cache(caches: &Caches, c: &DataCategory) -> &Mutex<LruCache<Vec<u8>, Vec<u8>>> {
+    caches
+        .get(c)
+        .expect("CachedDatabase::new configures a cache for every DataCategory")
+}
+
+impl<D: Database + 'static> Database for CachedDatabase<D> {
+    fn get(&self, c: DataCategory, key: &[u8]) -> FutRuntimeResult<Vec<u8>, DatabaseError> {
+        if let Some(value) = cache(&self.caches, &c).lock().unwrap().get(key) {
+            return Box::new(future::ok(value.clone()));
+        }
+
+        let inner = Arc::clone(&self.inner);
+        let caches = Arc::clone(&self.caches);
+        let key = key.to_vec();
+        let fut = future::lazy(move || {
+            let result = inner.get(c.clone(), &key).wait();
+            if let Ok(ref value) = result {
+                cache(&caches, &c).lock().unwrap().put(key, value.clone());
+            }
+            result
+        });
+        Box::new(fut)
+    }
+
+    fn get_batch(
+        &self,
+        c: DataCategory,
+        keys: &[Vec<u8>],
+    ) -> FutRuntimeResult<Vec<Option<Vec<u8>>>, DatabaseError> {
+        // Split into what the cache already has and what must be forwarded
+        // to the inner backend as a single batched lookup, rather than one
+        // point lookup per miss.
+        let mut values: Vec<Option<Option<Vec<u8>>>> = Vec::with_capacity(keys.len());
+        let mut misses = Vec::new();
+        {
+            let mut cache = cache(&self.caches, &c).lock().unwrap();
+            for key in keys {
+                values.push(cache.get(key).cloned().map(Some));
+                if values.last().unwrap().is_none() {
+                    misses.push(key.clone());
+                }
+            }
+        }
+
+        let inner = Arc::clone(&self.inner);
+        let caches = Arc::clone(&self.caches);
+        let keys = keys.to_vec();
+        let fut = future::lazy(move || {
+            let missed = inner.get_batch(c.clone(), &misses).wait()?;
+            let mut missed = missed.into_iter();
+
+            let mut cache = cache(&caches, &c).lock().unwrap();
+            let result = values
+                .into_iter()
+                .zip(keys.iter())
+                .map(|(cached, key)| match cached {
+                    Some(value) => Ok(value),
+                    None => {
+                        let value = missed.next().expect("one inner result per miss key");
+                        if let Some(ref value) = value {
+                            cache.put(key.clone(), value.clone());
+                        }
+                        Ok(value)
+                    }
+                })
+                .collect::<Result<Vec<_>, DatabaseError>>()?;
+            Ok(result)
+        });
+        Box::new(fut)
+    }
+
+    fn insert(&self, c: DataCategory, key: &[u8], value: &[u8]) -> FutRuntimeResult<(), DatabaseError> {
+        let inner = Arc::clone(&self.inner);
+        let caches = Arc::clone(&self.caches);
+        let key = key.to_vec();
+        let value = value.to_vec();
+        let fut = future::lazy(move || {
+            inner.insert(c.clone(), &key, &value).wait()?;
+            cache(&caches, &c).lock().unwrap().put(key, value);
+            Ok(())
+        });
+        Box::new(fut)
+    }
+
+    fn insert_batch(
+        &self,
+        c: DataCategory,
+        keys: &[Vec<u8>],
+        values: &[Vec<u8>],
+    ) -> FutRuntimeResult<(), DatabaseError> {
+        let inner = Arc::clone(&self.inner);
+        let caches = Arc::clone(&self.caches);
+        let keys = keys.to_vec();
+        let values = values.to_vec();
+        let fut = future::lazy(move || {
+            inner.insert_batch(c.clone(), &keys, &values).wait()?;
+            let mut cache = cache(&caches, &c).lock().unwrap();
+            for (key, value) in keys.into_iter().zip(values.into_iter()) {
+                cache.put(key, value);
+            }
+            Ok(())
+        });
+        Box::new(fut)
+    }
+
+    fn contains(&self, c: DataCategory, key: &[u8]) -> FutRuntimeResult<bool, DatabaseError> {
+        if cache(&self.caches, &c).lock().unwrap().contains(key) {
+            return Box::new(future::ok(true));
+        }
+
+        let inner = Arc::clone(&self.inner);
+        let caches = Arc::clone(&self.caches);
+        let key = key.to_vec();
+        let fut = future::lazy(move || match inner.get(c.clone(), &key).wait() {
+            Ok(value) => {
+                cache(&caches, &c).lock().unwrap().put(key, value);
+                Ok(true)
+            }
+            Err(DatabaseError::NotFound) => Ok(false),
+            Err(err) => Err(err),
+        });
+        Box::new(fut)
+    }
+
+    fn remove(&self, c: DataCategory, key: &[u8]) -> FutRuntimeResult<(), DatabaseError> {
+        let inner = Arc::clone(&self.inner);
+        let caches = Arc::clone(&self.caches);
+        let key = key.to_vec();
+        let fut = future::lazy(move || {
+            inner.remove(c.clone(), &key).wait()?;
+            cache(&caches, &c).lock().unwrap().pop(&key);
+            Ok(())
+        });
+        Box::new(fut)
+    }
+
+    fn remove_batch(&self, c: DataCategory, keys: &[Vec<u8>]) -> FutRuntimeResult<(), DatabaseError> {
+        let inner = Arc::clone(&self.inner);
+        let caches = Arc::clone(&self.caches);
+        let keys = keys.to_vec();
+        let fut = future::lazy(move || {
+            inner.remove_batch(c.clone(), &keys).wait()?;
+            let mut cache = cache(&caches, &c).lock().unwrap();
+            for key in &keys {
+                cache.pop(key);
+            }
+            Ok(())
+        });
+        Box::new(fut)
+    }
+
+    fn iter(&self, c: DataCategory, prefix: &[u8]) -> Result<DbIterator, DatabaseError> {
+        // Bulk range scans bypass the point-lookup cache and go straight to
+        // the inner backend's native iterator.
+        self.inner.iter(c, prefix)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use crate::memory::MemoryDB;
+
+    use super::*;
+
+    /// Wraps `MemoryDB` to count how many `get_batch` calls it actually
+    /// receives, so tests can tell a batched miss-lookup from one point
+    /// lookup per key.
+    #[derive(Default)]
+    struct CountingDB {
+        inner:           MemoryDB,
+        get_batch_calls: AtomicUsize,
+    }
+
+    impl Database for CountingDB {
+        fn get(&self, c: DataCategory, key: &[u8]) -> FutRuntimeResult<Vec<u8>, DatabaseError> {
+            self.inner.get(c, key)
+        }
+
+        fn get_batch(
+            &self,
+            c: DataCategory,
+            keys: &[Vec<u8>],
+        ) -> FutRuntimeResult<Vec<Option<Vec<u8>>>, DatabaseError> {
+            self.get_batch_calls.fetch_add(1, Ordering::SeqCst);
+            self.inner.get_batch(c, keys)
+        }
+
+        fn insert(&self, c: DataCategory, key: &[u8], value: &[u8]) -> FutRuntimeResult<(), DatabaseError> {
+            self.inner.insert(c, key, value)
+        }
+
+        fn insert_batch(
+            &self,
+            c: DataCategory,
+            keys: &[Vec<u8>],
+            values: &[Vec<u8>],
+        ) -> FutRuntimeResult<(), DatabaseError> {
+            self.inner.insert_batch(c, keys, values)
+        }
+
+        fn contains(&self, c: DataCategory, key: &[u8]) -> FutRuntimeResult<bool, DatabaseError> {
+            self.inner.contains(c, key)
+        }
+
+        fn remove(&self, c: DataCategory, key: &[u8]) -> FutRuntimeResult<(), DatabaseError> {
+            self.inner.remove(c, key)
+        }
+
+        fn remove_batch(&self, c: DataCategory, keys: &[Vec<u8>]) -> FutRuntimeResult<(), DatabaseError> {
+            self.inner.remove_batch(c, keys)
+        }
+
+        fn iter(&self, c: DataCategory, prefix: &[u8]) -> Result<DbIterator, DatabaseError> {
+            self.inner.iter(c, prefix)
+        }
+    }
+
+    #[test]
+    fn insert_then_get_round_trips_through_the_cache() {
+        let cached = CachedDatabase::new(CountingDB::default(), CacheConfig::default());
+        cached.insert(DataCategory::Block, b"key", b"value").wait().unwrap();
+
+        assert_eq!(cached.get(DataCategory::Block, b"key").wait().unwrap(), b"value");
+    }
+
+    #[test]
+    fn remove_invalidates_the_cache() {
+        let cached = CachedDatabase::new(CountingDB::default(), CacheConfig::default());
+        cached.insert(DataCategory::Block, b"key", b"value").wait().unwrap();
+        cached.remove(DataCategory::Block, b"key").wait().unwrap();
+
+        let err = cached.get(DataCategory::Block, b"key").wait().unwrap_err();
+        assert_eq!(err, DatabaseError::NotFound);
+    }
+
+    #[test]
+    fn get_batch_serves_cached_keys_without_forwarding_them_and_batches_the_rest() {
+        let db = CountingDB::default();
+        db.insert(DataCategory::Block, b"cached", b"from-cache").wait().unwrap();
+        let cached = CachedDatabase::new(db, CacheConfig::default());
+
+        // Warm the point-lookup cache for "cached".
+        cached.get(DataCategory::Block, b"cached").wait().unwrap();
+
+        // Insert "miss-1" directly against the inner db, bypassing
+        // `CachedDatabase::insert` (which would itself warm the cache), so
+        // it stays a genuine cache miss that `get_batch` must forward.
+        cached
+            .inner
+            .insert(DataCategory::Block, b"miss-1", b"value-1")
+            .wait()
+            .unwrap();
+        let result = cached
+            .get_batch(
+                DataCategory::Block,
+                &[b"cached".to_vec(), b"miss-1".to_vec(), b"missing".to_vec()],
+            )
+            .wait()
+            .unwrap();
+
+        assert_eq!(
+            result,
+            vec![Some(b"from-cache".to_vec()), Some(b"value-1".to_vec()), None]
+        );
+    }
+
+    #[test]
+    fn get_batch_forwards_all_misses_in_a_single_inner_call() {
+        let db = CountingDB::default();
+        let cached = CachedDatabase::new(db, CacheConfig::default());
+
+        cached
+            .get_batch(DataCategory::Block, &[b"a".to_vec(), b"b".to_vec(), b"c".to_vec()])
+            .wait()
+            .unwrap();
+
+        // Must go through `inner.get_batch` once for the whole miss set,
+        // never as three separate point lookups.
+        assert_eq!(cached.inner.get_batch_calls.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn get_batch_refreshes_lru_recency_like_get_does() {
+        let cached = CachedDatabase::new(CountingDB::default(), CacheConfig {
+            block: 2,
+            ..CacheConfig::default()
+        });
+        cached.insert(DataCategory::Block, b"a", b"value-a").wait().unwrap();
+        cached.insert(DataCategory::Block, b"b", b"value-b").wait().unwrap();
+
+        // Touch "a" via a batched read; if the cache-hit scan used `peek`
+        // instead of `get`, this wouldn't bump its recency, and the
+        // capacity-2 cache would evict "a" (not "b") on the next insert.
+        cached.get_batch(DataCategory::Block, &[b"a".to_vec()]).wait().unwrap();
+        cached.insert(DataCategory::Block, b"c", b"value-c").wait().unwrap();
+
+        let inner_cache = cache(&cached.caches, &DataCategory::Block).lock().unwrap();
+        assert!(inner_cache.contains(&b"a".to_vec()));
+        assert!(!inner_cache.contains(&b"b".to_vec()));
+    }
+}
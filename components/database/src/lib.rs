@@ -0,0 +1,3 @@
+pub mod cached;
+pub mod memory;
+pub mod rocks;
@@ -0,0 +1,96 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use futures01::future;
+
+use core_runtime::{DataCategory, Database, DatabaseError, DbIterator, FutRuntimeResult};
+
+/// In-memory `Database`, used in tests where no trie enumeration is needed.
+/// Does not support `iter`.
+#[derive(Default)]
+pub struct MemoryDB {
+    inner: Mutex<HashMap<DataCategory, HashMap<Vec<u8>, Vec<u8>>>>,
+}
+
+impl MemoryDB {
+    pub fn new() -> Self {
+        MemoryDB::default()
+    }
+}
+
+impl Database for MemoryDB {
+    fn get(&self, c: DataCategory, key: &[u8]) -> FutRuntimeResult<Vec<u8>, DatabaseError> {
+        let result = self
+            .inner
+            .lock()
+            .unwrap()
+            .get(&c)
+            .and_then(|m| m.get(key))
+            .cloned()
+            .ok_or(DatabaseError::NotFound);
+        Box::new(future::result(result))
+    }
+
+    fn get_batch(
+        &self,
+        c: DataCategory,
+        keys: &[Vec<u8>],
+    ) -> FutRuntimeResult<Vec<Option<Vec<u8>>>, DatabaseError> {
+        let inner = self.inner.lock().unwrap();
+        let map = inner.get(&c);
+        let result = Ok(keys
+            .iter()
+            .map(|key| map.and_then(|m| m.get(key)).cloned())
+            .collect());
+        Box::new(future::result(result))
+    }
+
+    fn insert(&self, c: DataCategory, key: &[u8], value: &[u8]) -> FutRuntimeResult<(), DatabaseError> {
+        self.inner
+            .lock()
+            .unwrap()
+            .entry(c)
+            .or_insert_with(HashMap::new)
+            .insert(key.to_vec(), value.to_vec());
+        Box::new(future::ok(()))
+    }
+
+    fn insert_batch(
+        &self,
+        c: DataCategory,
+        keys: &[Vec<u8>],
+        values: &[Vec<u8>],
+    ) -> FutRuntimeResult<(), DatabaseError> {
+        let mut inner = self.inner.lock().unwrap();
+        let map = inner.entry(c).or_insert_with(HashMap::new);
+        for (key, value) in keys.iter().zip(values.iter()) {
+            map.insert(key.clone(), value.clone());
+        }
+        Box::new(future::ok(()))
+    }
+
+    fn contains(&self, c: DataCategory, key: &[u8]) -> FutRuntimeResult<bool, DatabaseError> {
+        let result = self.inner.lock().unwrap().get(&c).map_or(false, |m| m.contains_key(key));
+        Box::new(future::ok(result))
+    }
+
+    fn remove(&self, c: DataCategory, key: &[u8]) -> FutRuntimeResult<(), DatabaseError> {
+        if let Some(map) = self.inner.lock().unwrap().get_mut(&c) {
+            map.remove(key);
+        }
+        Box::new(future::ok(()))
+    }
+
+    fn remove_batch(&self, c: DataCategory, keys: &[Vec<u8>]) -> FutRuntimeResult<(), DatabaseError> {
+        if let Some(map) = self.inner.lock().unwrap().get_mut(&c) {
+            for key in keys {
+                map.remove(key);
+            }
+        }
+        Box::new(future::ok(()))
+    }
+
+    fn iter(&self, _c: DataCategory, _prefix: &[u8]) -> Result<DbIterator, DatabaseError> {
+        Err(DatabaseError::Unsupported)
+    }
+}
@@ -0,0 +1,145 @@
+use futures01::future;
+use rocksdb::{ColumnFamily, Direction, IteratorMode, Options, DB};
+
+use core_runtime::{DataCategory, Database, DatabaseError, DbIterator, FutRuntimeResult};
+
+const COLUMN_FAMILIES: &[&str] = &[
+    "block",
+    "transaction",
+    "receipt",
+    "state",
+    "transaction_pool",
+    "journal",
+];
+
+fn column_name(c: &DataCategory) -> &'static str {
+    match c {
+        DataCategory::Block => "block",
+        DataCategory::Transaction => "transaction",
+        DataCategory::Receipt => "receipt",
+        DataCategory::State => "state",
+        DataCategory::TransactionPool => "transaction_pool",
+        DataCategory::Journal => "journal",
+    }
+}
+
+pub struct RocksDB {
+    db: DB,
+}
+
+impl RocksDB {
+    pub fn new(path: &str) -> Result<Self, DatabaseError> {
+        let mut opts = Options::default();
+        opts.create_if_missing(true);
+        opts.create_missing_column_families(true);
+
+        let db =
+            DB::open_cf(&opts, path, COLUMN_FAMILIES).map_err(|e| DatabaseError::Internal(e.to_string()))?;
+        Ok(RocksDB { db })
+    }
+
+    fn cf(&self, c: &DataCategory) -> Result<&ColumnFamily, DatabaseError> {
+        self.db
+            .cf_handle(column_name(c))
+            .ok_or_else(|| DatabaseError::Internal(format!("missing column family for {:?}", c)))
+    }
+}
+
+impl Database for RocksDB {
+    fn get(&self, c: DataCategory, key: &[u8]) -> FutRuntimeResult<Vec<u8>, DatabaseError> {
+        let result = self.cf(&c).and_then(|cf| {
+            self.db
+                .get_cf(cf, key)
+                .map_err(|e| DatabaseError::Internal(e.to_string()))?
+                .map(|v| v.to_vec())
+                .ok_or(DatabaseError::NotFound)
+        });
+        Box::new(future::result(result))
+    }
+
+    fn get_batch(
+        &self,
+        c: DataCategory,
+        keys: &[Vec<u8>],
+    ) -> FutRuntimeResult<Vec<Option<Vec<u8>>>, DatabaseError> {
+        let result = self.cf(&c).and_then(|cf| {
+            keys.iter()
+                .map(|key| {
+                    self.db
+                        .get_cf(cf, key)
+                        .map(|v| v.map(|v| v.to_vec()))
+                        .map_err(|e| DatabaseError::Internal(e.to_string()))
+                })
+                .collect()
+        });
+        Box::new(future::result(result))
+    }
+
+    fn insert(&self, c: DataCategory, key: &[u8], value: &[u8]) -> FutRuntimeResult<(), DatabaseError> {
+        let result = self
+            .cf(&c)
+            .and_then(|cf| self.db.put_cf(cf, key, value).map_err(|e| DatabaseError::Internal(e.to_string())));
+        Box::new(future::result(result))
+    }
+
+    fn insert_batch(
+        &self,
+        c: DataCategory,
+        keys: &[Vec<u8>],
+        values: &[Vec<u8>],
+    ) -> FutRuntimeResult<(), DatabaseError> {
+        let result = self.cf(&c).and_then(|cf| {
+            let mut batch = rocksdb::WriteBatch::default();
+            for (key, value) in keys.iter().zip(values.iter()) {
+                batch
+                    .put_cf(cf, key, value)
+                    .map_err(|e| DatabaseError::Internal(e.to_string()))?;
+            }
+            self.db.write(batch).map_err(|e| DatabaseError::Internal(e.to_string()))
+        });
+        Box::new(future::result(result))
+    }
+
+    fn contains(&self, c: DataCategory, key: &[u8]) -> FutRuntimeResult<bool, DatabaseError> {
+        let result = self
+            .cf(&c)
+            .and_then(|cf| self.db.get_cf(cf, key).map(|v| v.is_some()).map_err(|e| DatabaseError::Internal(e.to_string())));
+        Box::new(future::result(result))
+    }
+
+    fn remove(&self, c: DataCategory, key: &[u8]) -> FutRuntimeResult<(), DatabaseError> {
+        let result = self
+            .cf(&c)
+            .and_then(|cf| self.db.delete_cf(cf, key).map_err(|e| DatabaseError::Internal(e.to_string())));
+        Box::new(future::result(result))
+    }
+
+    fn remove_batch(&self, c: DataCategory, keys: &[Vec<u8>]) -> FutRuntimeResult<(), DatabaseError> {
+        let result = self.cf(&c).and_then(|cf| {
+            let mut batch = rocksdb::WriteBatch::default();
+            for key in keys {
+                batch.delete_cf(cf, key).map_err(|e| DatabaseError::Internal(e.to_string()))?;
+            }
+            self.db.write(batch).map_err(|e| DatabaseError::Internal(e.to_string()))
+        });
+        Box::new(future::result(result))
+    }
+
+    fn iter(&self, c: DataCategory, prefix: &[u8]) -> Result<DbIterator, DatabaseError> {
+        // `prefix` is an arbitrary caller-chosen byte string, not a
+        // fixed-length key fragment, so there's no single prefix extractor
+        // to configure on the column family for `prefix_iterator_cf`'s
+        // bloom filter to key off of. Seek to `prefix` with the plain
+        // iterator instead and stop once a key no longer starts with it.
+        let cf = self.cf(&c)?;
+        let prefix = prefix.to_vec();
+        let iter = self
+            .db
+            .iterator_cf(cf, IteratorMode::From(&prefix, Direction::Forward))
+            .map_err(|e| DatabaseError::Internal(e.to_string()))?
+            .take_while(move |(key, _)| key.starts_with(&prefix))
+            .map(|(key, value)| (key.to_vec(), value.to_vec()));
+
+        Ok(Box::new(iter))
+    }
+}
@@ -0,0 +1,6 @@
+mod trie;
+
+pub mod evm;
+pub mod proof;
+
+pub use crate::trie::{TrieDB, TrieDbError, TrieNode};
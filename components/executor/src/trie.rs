@@ -0,0 +1,181 @@
+use std::sync::Arc;
+
+use futures01::future::Future;
+use serde_derive::{Deserialize, Serialize};
+
+use core_runtime::{DataCategory, Database, DatabaseError};
+use core_types::Hash;
+
+#[derive(Debug)]
+pub enum TrieDbError {
+    Database(DatabaseError),
+    Decode(String),
+    NodeNotFound(Hash),
+}
+
+impl From<DatabaseError> for TrieDbError {
+    fn from(err: DatabaseError) -> Self {
+        TrieDbError::Database(err)
+    }
+}
+
+/// A node of the state trie, content-addressed by the hash of its own
+/// encoding: a `Leaf` carries the stored value directly (an account or a
+/// storage slot); a `Branch` fans out to up to 16 children by hash.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum TrieNode {
+    Leaf(Vec<u8>),
+    Branch(Vec<Option<Hash>>),
+}
+
+impl TrieNode {
+    pub fn children(&self) -> Vec<Hash> {
+        match self {
+            TrieNode::Leaf(_) => Vec::new(),
+            TrieNode::Branch(children) => children.iter().filter_map(Clone::clone).collect(),
+        }
+    }
+}
+
+const PREIMAGE_KEY_PREFIX: &[u8] = b"preimage:";
+
+fn preimage_key(hash: &Hash) -> Vec<u8> {
+    let mut key = PREIMAGE_KEY_PREFIX.to_vec();
+    key.extend_from_slice(hash.as_bytes());
+    key
+}
+
+/// The state trie, stored as content-addressed nodes under
+/// `DataCategory::State`: `key = hash(encoded node)`.
+pub struct TrieDB<DB> {
+    db:     Arc<DB>,
+    fat_db: bool,
+}
+
+impl<DB: Database> TrieDB<DB> {
+    pub fn new(db: Arc<DB>) -> Self {
+        TrieDB { db, fat_db: false }
+    }
+
+    /// Builds a `TrieDB` with "fat DB" mode set: when enabled,
+    /// `record_preimage` actually persists preimages (account addresses
+    /// and storage slots) of hashed trie keys, at the cost of roughly
+    /// doubling write volume for every key touched.
+    pub fn with_fat_db(db: Arc<DB>, fat_db: bool) -> Self {
+        TrieDB { db, fat_db }
+    }
+
+    pub fn is_fat_db(&self) -> bool {
+        self.fat_db
+    }
+
+    /// Records the preimage of a hashed trie key, under `DataCategory::
+    /// Journal` rather than `State` so that prefix-scanning `State` for a
+    /// full state dump never turns up anything but actual trie nodes. A
+    /// no-op unless fat DB mode is enabled.
+    pub fn record_preimage(&self, hash: &Hash, preimage: &[u8]) -> Result<(), TrieDbError> {
+        if !self.fat_db {
+            return Ok(());
+        }
+        self.db.insert(DataCategory::Journal, &preimage_key(hash), preimage).wait()?;
+        Ok(())
+    }
+
+    /// Looks up a preimage previously recorded by `record_preimage`.
+    /// Always `Ok(None)` when fat DB mode was never enabled.
+    pub fn get_preimage(&self, hash: &Hash) -> Result<Option<Vec<u8>>, TrieDbError> {
+        match self.db.get(DataCategory::Journal, &preimage_key(hash)).wait() {
+            Ok(raw) => Ok(Some(raw)),
+            Err(DatabaseError::NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn get_node(&self, hash: &Hash) -> Result<TrieNode, TrieDbError> {
+        let raw = self.db.get(DataCategory::State, hash.as_bytes()).wait()?;
+        serde_json::from_slice(&raw).map_err(|e| TrieDbError::Decode(e.to_string()))
+    }
+
+    pub fn insert_node(&self, node: &TrieNode) -> Result<Hash, TrieDbError> {
+        let raw = serde_json::to_vec(node).map_err(|e| TrieDbError::Decode(e.to_string()))?;
+        let hash = Hash::digest(&raw);
+        self.db.insert(DataCategory::State, hash.as_bytes(), &raw).wait()?;
+        Ok(hash)
+    }
+
+    pub fn contains_node(&self, hash: &Hash) -> Result<bool, TrieDbError> {
+        Ok(self.db.contains(DataCategory::State, hash.as_bytes()).wait()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use components_database::memory::MemoryDB;
+
+    use super::*;
+
+    #[test]
+    fn insert_and_get_node_round_trip() {
+        let trie_db = TrieDB::new(Arc::new(MemoryDB::new()));
+        let node = TrieNode::Leaf(b"balance".to_vec());
+
+        let hash = trie_db.insert_node(&node).unwrap();
+
+        assert!(trie_db.contains_node(&hash).unwrap());
+        assert_eq!(trie_db.get_node(&hash).unwrap(), node);
+    }
+
+    #[test]
+    fn record_preimage_is_a_no_op_without_fat_db() {
+        let trie_db = TrieDB::new(Arc::new(MemoryDB::new()));
+        let hash = Hash::digest(b"some-key");
+
+        trie_db.record_preimage(&hash, b"address").unwrap();
+
+        assert_eq!(trie_db.get_preimage(&hash).unwrap(), None);
+    }
+
+    #[test]
+    fn record_preimage_round_trips_under_fat_db() {
+        let trie_db = TrieDB::with_fat_db(Arc::new(MemoryDB::new()), true);
+        let hash = Hash::digest(b"some-key");
+
+        trie_db.record_preimage(&hash, b"address").unwrap();
+
+        assert_eq!(trie_db.get_preimage(&hash).unwrap(), Some(b"address".to_vec()));
+    }
+
+    #[test]
+    fn preimages_live_outside_the_state_keyspace() {
+        let db = Arc::new(MemoryDB::new());
+        let trie_db = TrieDB::with_fat_db(Arc::clone(&db), true);
+        let hash = Hash::digest(b"some-key");
+
+        trie_db.record_preimage(&hash, b"address").unwrap();
+
+        // A full-state dump prefix-scans `DataCategory::State`; the
+        // preimage bookkeeping entry must not show up there.
+        assert_eq!(
+            db.get(DataCategory::State, &preimage_key(&hash)).wait(),
+            Err(DatabaseError::NotFound)
+        );
+        assert!(db.get(DataCategory::Journal, &preimage_key(&hash)).wait().is_ok());
+    }
+}
+
+impl<DB: Database> core_storage::snapshot::TrieReader for TrieDB<DB> {
+    fn read_node(&self, hash: &Hash) -> Result<(Vec<u8>, Vec<Hash>), core_storage::snapshot::SnapshotError> {
+        let node = self
+            .get_node(hash)
+            .map_err(|e| core_storage::snapshot::SnapshotError::Encode(format!("{:?}", e)))?;
+        let children = node.children();
+        let raw = serde_json::to_vec(&node)
+            .map_err(|e| core_storage::snapshot::SnapshotError::Encode(e.to_string()))?;
+        Ok((raw, children))
+    }
+
+    fn has_node(&self, hash: &Hash) -> Result<bool, core_storage::snapshot::SnapshotError> {
+        self.contains_node(hash)
+            .map_err(|e| core_storage::snapshot::SnapshotError::Encode(format!("{:?}", e)))
+    }
+}
@@ -0,0 +1,217 @@
+use futures01::future::Future;
+use serde_derive::Serialize;
+
+use core_context::Context;
+use core_storage::{Storage, StorageError};
+use core_types::TxFeeSample;
+
+/// Response for `eth_feeHistory`. `base_fee_per_gas` has one more entry
+/// than `gas_used_ratio`/`reward`: the extra trailing entry is the base fee
+/// the *next* (pending) block would pay.
+#[derive(Debug, Clone, Serialize)]
+pub struct FeeHistory {
+    pub oldest_block:     u64,
+    pub base_fee_per_gas: Vec<u64>,
+    pub gas_used_ratio:   Vec<f64>,
+    pub reward:           Option<Vec<Vec<u64>>>,
+}
+
+/// `block_count` is capped at this many blocks, the same limit geth uses
+/// for `eth_feeHistory`, so a caller can't force a multi-gigabyte
+/// allocation or an unbounded walk over the block store.
+pub const MAX_FEE_HISTORY_BLOCK_COUNT: u64 = 1024;
+
+/// Implements `eth_feeHistory(block_count, newest_block, reward_percentiles)`.
+///
+/// Walks `block_count` headers back from `newest_block`. A requested height
+/// whose block can't be loaded from `storage` (one that predates fee
+/// tracking, or lies outside the stored chain) is reported as
+/// `base_fee_floor` with a zero gas-used ratio instead of being rejected.
+pub fn fee_history<S: Storage>(
+    ctx: Context,
+    storage: &S,
+    base_fee_floor: u64,
+    block_count: u64,
+    newest_block: u64,
+    reward_percentiles: Option<&[f64]>,
+) -> Result<FeeHistory, StorageError> {
+    let block_count = block_count.max(1).min(MAX_FEE_HISTORY_BLOCK_COUNT);
+    let oldest_block = newest_block.saturating_sub(block_count - 1);
+
+    let mut base_fee_per_gas = Vec::with_capacity((block_count + 1) as usize);
+    let mut gas_used_ratio = Vec::with_capacity(block_count as usize);
+    let mut reward = reward_percentiles.map(|p| Vec::with_capacity(p.len()));
+    let mut newest_header = None;
+
+    for height in oldest_block..=newest_block {
+        match storage.get_block_by_height(ctx.clone(), height).wait() {
+            Ok(block) => {
+                base_fee_per_gas.push(block.header.base_fee_per_gas);
+                gas_used_ratio.push(block.header.gas_used as f64 / block.header.quota_limit as f64);
+
+                if let (Some(reward), Some(percentiles)) = (reward.as_mut(), reward_percentiles) {
+                    reward.push(rewards_at_percentiles(&block.tx_fee_samples, percentiles));
+                }
+
+                newest_header = Some(block.header);
+            }
+            // Height predates fee tracking (or the chain itself): report the floor.
+            Err(_) => {
+                base_fee_per_gas.push(base_fee_floor);
+                gas_used_ratio.push(0f64);
+                if let (Some(reward), Some(percentiles)) = (reward.as_mut(), reward_percentiles) {
+                    reward.push(vec![base_fee_floor; percentiles.len()]);
+                }
+            }
+        }
+    }
+
+    let next_base_fee = newest_header
+        .map(|header| header.next_base_fee_per_gas(base_fee_floor))
+        .unwrap_or(base_fee_floor);
+    base_fee_per_gas.push(next_base_fee);
+
+    Ok(FeeHistory {
+        oldest_block,
+        base_fee_per_gas,
+        gas_used_ratio,
+        reward,
+    })
+}
+
+/// Sorts samples by effective priority fee and, for each requested
+/// cumulative-gas percentile, returns the fee of the first sample whose gas
+/// brings the running total to or past that percentile of the block's
+/// total gas.
+fn rewards_at_percentiles(samples: &[TxFeeSample], percentiles: &[f64]) -> Vec<u64> {
+    let mut sorted = samples.to_vec();
+    sorted.sort_by_key(|sample| sample.effective_priority_fee);
+
+    let total_gas: u64 = sorted.iter().map(|sample| sample.gas_used).sum();
+    if total_gas == 0 {
+        return vec![0; percentiles.len()];
+    }
+
+    percentiles
+        .iter()
+        .map(|percentile| {
+            let target = (total_gas as f64 * percentile / 100.0) as u64;
+            let mut cumulative = 0u64;
+            for sample in &sorted {
+                cumulative += sample.gas_used;
+                if cumulative >= target {
+                    return sample.effective_priority_fee;
+                }
+            }
+            sorted.last().map(|s| s.effective_priority_fee).unwrap_or(0)
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(effective_priority_fee: u64, gas_used: u64) -> TxFeeSample {
+        TxFeeSample {
+            effective_priority_fee,
+            gas_used,
+        }
+    }
+
+    #[test]
+    fn rewards_at_percentiles_picks_sample_crossing_cumulative_gas() {
+        let samples = vec![sample(1, 50), sample(2, 50), sample(3, 100)];
+        // total gas = 200; 50th percentile target = 100, crossed by the
+        // second sample (cumulative 100) once sorted by fee.
+        assert_eq!(rewards_at_percentiles(&samples, &[0.0, 50.0, 100.0]), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn rewards_at_percentiles_with_no_gas_returns_zero() {
+        let samples: Vec<TxFeeSample> = vec![];
+        assert_eq!(rewards_at_percentiles(&samples, &[10.0, 90.0]), vec![0, 0]);
+    }
+
+    struct FakeStorage {
+        blocks: std::collections::HashMap<u64, core_types::Block>,
+    }
+
+    impl Storage for FakeStorage {
+        fn get_latest_block(&self, _ctx: Context) -> core_runtime::FutRuntimeResult<core_types::Block, StorageError> {
+            unimplemented!("not exercised by these tests")
+        }
+
+        fn get_block_by_height(
+            &self,
+            _ctx: Context,
+            height: u64,
+        ) -> core_runtime::FutRuntimeResult<core_types::Block, StorageError> {
+            match self.blocks.get(&height) {
+                Some(block) => Box::new(futures01::future::ok(block.clone())),
+                None => Box::new(futures01::future::err(StorageError::Encode("no such height".to_owned()))),
+            }
+        }
+
+        fn insert_block(&self, _ctx: Context, _block: core_types::Block) -> core_runtime::FutRuntimeResult<(), StorageError> {
+            unimplemented!("not exercised by these tests")
+        }
+
+        fn get_latest_proof(&self, _ctx: Context) -> core_runtime::FutRuntimeResult<core_types::Proof, StorageError> {
+            unimplemented!("not exercised by these tests")
+        }
+
+        fn update_latest_proof(
+            &self,
+            _ctx: Context,
+            _proof: core_types::Proof,
+        ) -> core_runtime::FutRuntimeResult<(), StorageError> {
+            unimplemented!("not exercised by these tests")
+        }
+    }
+
+    #[test]
+    fn fee_history_clamps_block_count_and_reports_next_base_fee() {
+        let mut blocks = std::collections::HashMap::new();
+        blocks.insert(
+            5,
+            core_types::Block {
+                header: core_types::BlockHeader {
+                    gas_used: 500,
+                    quota_limit: 1000,
+                    base_fee_per_gas: 100,
+                    height: 5,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+        );
+        let storage = FakeStorage { blocks };
+
+        let history = fee_history(
+            Context::new(),
+            &storage,
+            1,
+            MAX_FEE_HISTORY_BLOCK_COUNT * 10,
+            5,
+            None,
+        )
+        .unwrap();
+
+        // block_count is clamped, so oldest_block can't walk past the cap.
+        assert_eq!(history.oldest_block, 5u64.saturating_sub(MAX_FEE_HISTORY_BLOCK_COUNT - 1));
+        assert_eq!(*history.base_fee_per_gas.last().unwrap(), 100);
+    }
+
+    #[test]
+    fn fee_history_reports_floor_for_heights_missing_from_storage() {
+        let storage = FakeStorage {
+            blocks: std::collections::HashMap::new(),
+        };
+
+        let history = fee_history(Context::new(), &storage, 7, 3, 10, None).unwrap();
+
+        assert_eq!(history.base_fee_per_gas, vec![7, 7, 7, 7]);
+        assert_eq!(history.gas_used_ratio, vec![0.0, 0.0, 0.0]);
+    }
+}
@@ -0,0 +1,285 @@
+use std::io::{BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+use serde_derive::{Deserialize, Serialize};
+
+use components_executor::evm::EVMExecutor;
+use core_context::Context;
+use core_network::proof::ProofSource;
+use core_runtime::Database;
+use core_storage::Storage;
+use core_types::{BlockHeader, Hash};
+
+mod eth;
+mod light;
+mod state;
+
+pub use crate::eth::{fee_history, FeeHistory, MAX_FEE_HISTORY_BLOCK_COUNT};
+pub use crate::light::{account_key_hash, query_state, storage_key_hash, LightQueryError};
+pub use crate::state::{account_range, dump_state, StateEntry, StateQueryError};
+
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub listen:  String,
+    pub workers: usize,
+    /// Floor below which `base_fee_per_gas` is never allowed to drop.
+    pub base_fee_floor: u64,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            listen:         "127.0.0.1:8000".to_owned(),
+            workers:        1,
+            base_fee_floor: 1,
+        }
+    }
+}
+
+/// One line of a request over the line-delimited JSON-RPC connection
+/// `listen` accepts. Tagged by `method` so a caller's JSON looks like
+/// `{"method": "eth_feeHistory", "params": {...}}`.
+#[derive(Debug, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "camelCase")]
+pub enum Request {
+    #[serde(rename = "eth_feeHistory")]
+    FeeHistory {
+        block_count:        u64,
+        newest_block:       u64,
+        reward_percentiles: Option<Vec<f64>>,
+    },
+    #[serde(rename = "debug_dumpState")]
+    DumpState,
+    #[serde(rename = "debug_accountRange")]
+    AccountRange { prefix: Vec<u8> },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(untagged)]
+pub enum Response {
+    FeeHistory(FeeHistory),
+    State(Vec<StateEntry>),
+}
+
+#[derive(Debug)]
+pub enum DispatchError {
+    InvalidRequest(String),
+    Storage(StorageDispatchError),
+}
+
+#[derive(Debug)]
+pub enum StorageDispatchError {
+    Storage(core_storage::StorageError),
+    State(StateQueryError),
+}
+
+pub struct AppState<DB, P, S> {
+    pub executor: Arc<EVMExecutor<DB, S>>,
+    pub state_db: Arc<DB>,
+    pub tx_pool:  Arc<P>,
+    pub storage:  Arc<S>,
+    pub base_fee_floor: u64,
+}
+
+impl<DB: Database, P, S: Storage> AppState<DB, P, S> {
+    pub fn new(
+        executor: Arc<EVMExecutor<DB, S>>,
+        state_db: Arc<DB>,
+        tx_pool: Arc<P>,
+        storage: Arc<S>,
+        base_fee_floor: u64,
+    ) -> Self {
+        AppState {
+            executor,
+            state_db,
+            tx_pool,
+            storage,
+            base_fee_floor,
+        }
+    }
+}
+
+/// Answers one `Request`, the single place `eth_feeHistory`,
+/// `debug_dumpState` and `debug_accountRange` are actually reachable from.
+pub fn dispatch<DB: Database, P, S: Storage>(
+    state: &AppState<DB, P, S>,
+    request: Request,
+) -> Result<Response, StorageDispatchError> {
+    match request {
+        Request::FeeHistory {
+            block_count,
+            newest_block,
+            reward_percentiles,
+        } => {
+            let history = fee_history(
+                Context::new(),
+                &*state.storage,
+                state.base_fee_floor,
+                block_count,
+                newest_block,
+                reward_percentiles.as_deref(),
+            )
+            .map_err(StorageDispatchError::Storage)?;
+            Ok(Response::FeeHistory(history))
+        }
+        Request::DumpState => {
+            let entries =
+                dump_state(&*state.state_db, state.executor.trie_db()).map_err(StorageDispatchError::State)?;
+            Ok(Response::State(entries))
+        }
+        Request::AccountRange { prefix } => {
+            let entries = account_range(&*state.state_db, state.executor.trie_db(), &prefix)
+                .map_err(StorageDispatchError::State)?;
+            Ok(Response::State(entries))
+        }
+    }
+}
+
+/// Binds `config.listen` and answers one line-delimited JSON `Request` per
+/// line on each accepted connection, across `config.workers` threads
+/// sharing the listener.
+pub fn listen<DB, P, S>(config: Config, state: AppState<DB, P, S>) -> std::io::Result<()>
+where
+    DB: Database + 'static,
+    P: Send + Sync + 'static,
+    S: Storage + 'static,
+{
+    let state = Arc::new(state);
+    let listener = TcpListener::bind(&config.listen)?;
+
+    let mut workers = Vec::with_capacity(config.workers.max(1));
+    for _ in 0..config.workers.max(1) {
+        let listener = listener.try_clone()?;
+        let state = Arc::clone(&state);
+        workers.push(thread::spawn(move || {
+            for stream in listener.incoming() {
+                if let Ok(stream) = stream {
+                    serve_connection(&state, stream);
+                }
+            }
+        }));
+    }
+    for worker in workers {
+        let _ = worker.join();
+    }
+    Ok(())
+}
+
+fn serve_connection<DB: Database, P, S: Storage>(state: &AppState<DB, P, S>, stream: TcpStream) {
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(_) => return,
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if line.is_empty() {
+            continue;
+        }
+
+        let outcome = serde_json::from_str::<Request>(&line)
+            .map_err(|e| DispatchError::InvalidRequest(e.to_string()))
+            .and_then(|request| dispatch(state, request).map_err(DispatchError::Storage));
+
+        let body = match outcome {
+            Ok(response) => serde_json::to_string(&response),
+            Err(err) => serde_json::to_string(&format!("{:?}", err)),
+        };
+        match body {
+            Ok(body) if writeln!(writer, "{}", body).is_ok() => continue,
+            _ => break,
+        }
+    }
+}
+
+/// RPC server state for light mode: no executor or storage, just a trusted
+/// header and a way to fetch Merkle proofs for the state queries it
+/// answers against that header's `state_root`.
+pub struct LightAppState<P> {
+    pub proof_source:   Arc<P>,
+    pub trusted_header: BlockHeader,
+}
+
+impl<P: ProofSource> LightAppState<P> {
+    pub fn new(proof_source: Arc<P>, trusted_header: BlockHeader) -> Self {
+        LightAppState {
+            proof_source,
+            trusted_header,
+        }
+    }
+}
+
+/// A light-mode request: a single state key to fetch and verify a Merkle
+/// proof for, as of `LightAppState::trusted_header.state_root`.
+#[derive(Debug, Deserialize)]
+pub struct LightRequest {
+    pub key_hash: Hash,
+}
+
+/// Answers one `LightRequest` via `query_state` — the single place a light
+/// node's state queries are actually reachable from.
+pub fn dispatch_light<P: ProofSource>(state: &LightAppState<P>, request: LightRequest) -> Result<Vec<u8>, LightQueryError> {
+    query_state(&*state.proof_source, &state.trusted_header, request.key_hash)
+}
+
+/// Binds `config.listen` and answers one line-delimited JSON `LightRequest`
+/// per line on each accepted connection, across `config.workers` threads
+/// sharing the listener.
+pub fn listen_light<P>(config: Config, state: LightAppState<P>) -> std::io::Result<()>
+where
+    P: ProofSource + 'static,
+{
+    let state = Arc::new(state);
+    let listener = TcpListener::bind(&config.listen)?;
+
+    let mut workers = Vec::with_capacity(config.workers.max(1));
+    for _ in 0..config.workers.max(1) {
+        let listener = listener.try_clone()?;
+        let state = Arc::clone(&state);
+        workers.push(thread::spawn(move || {
+            for stream in listener.incoming() {
+                if let Ok(stream) = stream {
+                    serve_light_connection(&state, stream);
+                }
+            }
+        }));
+    }
+    for worker in workers {
+        let _ = worker.join();
+    }
+    Ok(())
+}
+
+fn serve_light_connection<P: ProofSource>(state: &LightAppState<P>, stream: TcpStream) {
+    let mut writer = match stream.try_clone() {
+        Ok(writer) => writer,
+        Err(_) => return,
+    };
+
+    for line in BufReader::new(stream).lines() {
+        let line = match line {
+            Ok(line) => line,
+            Err(_) => break,
+        };
+        if line.is_empty() {
+            continue;
+        }
+
+        let body = match serde_json::from_str::<LightRequest>(&line) {
+            Ok(request) => match dispatch_light(state, request) {
+                Ok(value) => serde_json::to_string(&value),
+                Err(err) => serde_json::to_string(&format!("{:?}", err)),
+            },
+            Err(err) => serde_json::to_string(&format!("invalid request: {}", err)),
+        };
+        match body {
+            Ok(body) if writeln!(writer, "{}", body).is_ok() => continue,
+            _ => break,
+        }
+    }
+}
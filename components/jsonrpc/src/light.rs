@@ -0,0 +1,55 @@
+use components_executor::proof::{verify_merkle_proof, ProofError};
+use core_network::proof::{GetProofRequest, ProofFetchError, ProofSource};
+use core_types::{Address, BlockHeader, Hash};
+
+#[derive(Debug)]
+pub enum LightQueryError {
+    Proof(ProofError),
+    Network(ProofFetchError),
+}
+
+impl From<ProofFetchError> for LightQueryError {
+    fn from(err: ProofFetchError) -> Self {
+        LightQueryError::Network(err)
+    }
+}
+
+/// Answers a balance/storage/code query in light mode: requests a Merkle
+/// proof for `key_hash` as of `trusted_header.state_root` from a connected
+/// full node, then verifies it locally rather than trusting the answer.
+pub fn query_state<P: ProofSource>(
+    source: &P,
+    trusted_header: &BlockHeader,
+    key_hash: Hash,
+) -> Result<Vec<u8>, LightQueryError> {
+    let response = source.request_proof(GetProofRequest {
+        state_root: trusted_header.state_root.clone(),
+        key_hash: key_hash.clone(),
+    })?;
+
+    let leaf = response
+        .nodes
+        .last()
+        .ok_or(LightQueryError::Proof(ProofError::BrokenChain))?;
+    let leaf_hash = Hash::digest(leaf);
+    if leaf_hash != key_hash {
+        return Err(LightQueryError::Proof(ProofError::HashMismatch {
+            expected: key_hash,
+            got:      leaf_hash,
+        }));
+    }
+
+    verify_merkle_proof(&trusted_header.state_root, &response.nodes).map_err(LightQueryError::Proof)
+}
+
+/// The trie key an account's balance/nonce/code lives under.
+pub fn account_key_hash(address: &Address) -> Hash {
+    Hash::digest(address.as_bytes())
+}
+
+/// The trie key one of an account's storage slots lives under.
+pub fn storage_key_hash(address: &Address, slot: &Hash) -> Hash {
+    let mut preimage = address.as_bytes().to_vec();
+    preimage.extend_from_slice(slot.as_bytes());
+    Hash::digest(&preimage)
+}
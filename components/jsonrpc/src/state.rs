@@ -0,0 +1,59 @@
+use serde_derive::Serialize;
+
+use components_executor::TrieDB;
+use core_runtime::{DataCategory, Database, DatabaseError};
+use core_types::{Address, Hash};
+
+#[derive(Debug)]
+pub enum StateQueryError {
+    Database(DatabaseError),
+    /// The underlying `Database` doesn't support `iter` (only RocksDB does).
+    Unsupported,
+}
+
+impl From<DatabaseError> for StateQueryError {
+    fn from(err: DatabaseError) -> Self {
+        match err {
+            DatabaseError::Unsupported => StateQueryError::Unsupported,
+            other => StateQueryError::Database(other),
+        }
+    }
+}
+
+/// One entry of a full state dump. `address` is only populated when fat DB
+/// mode recorded the preimage of this entry's hashed trie key; otherwise
+/// the entry is only reachable by its raw hash.
+#[derive(Debug, Clone, Serialize)]
+pub struct StateEntry {
+    pub address: Option<Address>,
+    pub raw:     Vec<u8>,
+}
+
+/// Dumps every entry under `DataCategory::State`, resolving hashed keys
+/// back to addresses via the fat-DB preimage table where available. Backed
+/// by `Database::iter`, so it only works against the RocksDB backend.
+pub fn dump_state<DB: Database>(db: &DB, trie_db: &TrieDB<DB>) -> Result<Vec<StateEntry>, StateQueryError> {
+    account_range(db, trie_db, &[])
+}
+
+/// Dumps every entry under `DataCategory::State` whose key starts with
+/// `prefix`, the primitive range queries and account/storage enumeration
+/// are built on.
+pub fn account_range<DB: Database>(
+    db: &DB,
+    trie_db: &TrieDB<DB>,
+    prefix: &[u8],
+) -> Result<Vec<StateEntry>, StateQueryError> {
+    let entries = db
+        .iter(DataCategory::State, prefix)?
+        .map(|(key, value)| {
+            let address = trie_db
+                .get_preimage(&Hash::from_bytes(key))
+                .ok()
+                .flatten()
+                .map(Address::from_bytes);
+            StateEntry { address, raw: value }
+        })
+        .collect();
+    Ok(entries)
+}
@@ -0,0 +1,210 @@
+use std::collections::HashSet;
+
+use secp256k1::recovery::{RecoverableSignature, RecoveryId};
+use secp256k1::{Message, Secp256k1, VerifyOnly};
+
+use core_types::{Address, Hash, Proof};
+
+/// The message each verifier actually signs for a given proof: its height,
+/// round and block hash. Binding all three prevents a signature collected
+/// for one block or round from being replayed as a commit for another.
+fn proof_digest(proof: &Proof) -> Hash {
+    let mut preimage = proof.height.to_be_bytes().to_vec();
+    preimage.extend_from_slice(&proof.round.to_be_bytes());
+    preimage.extend_from_slice(proof.block_hash.as_bytes());
+    Hash::digest(&preimage)
+}
+
+/// Recovers the address that produced a 65-byte recoverable ECDSA
+/// signature (`r || s || recovery_id`) over `digest`. Returns `None` for
+/// anything that doesn't parse or doesn't recover to a valid point,
+/// rather than erroring, since a malformed commit should just fail to
+/// count towards quorum in `verify_proof`.
+fn recover_signer(digest: &Hash, signature: &[u8]) -> Option<Address> {
+    if signature.len() != 65 {
+        return None;
+    }
+
+    let recovery_id = RecoveryId::from_i32(i32::from(signature[64])).ok()?;
+    let recoverable = RecoverableSignature::from_compact(&signature[..64], recovery_id).ok()?;
+    let message = Message::from_slice(digest.as_bytes()).ok()?;
+
+    let ctx: Secp256k1<VerifyOnly> = Secp256k1::verification_only();
+    let pubkey = ctx.recover(&message, &recoverable).ok()?;
+
+    let address_hash = Hash::digest(&pubkey.serialize_uncompressed()[1..]);
+    Some(Address::from_bytes(address_hash.as_bytes()[12..].to_vec()))
+}
+
+/// Verifies a BFT proof was produced by a quorum of `verifier_list`: more
+/// than two thirds of the listed verifiers must each contribute a
+/// `Commit` whose signature actually recovers to their own address over
+/// `(height, round, block_hash)`. A commit from an address outside
+/// `verifier_list`, a duplicate commit, or a signature that doesn't
+/// recover to the address it claims, doesn't count towards quorum. This
+/// is the single entry point both the full `Engine`'s commit path and a
+/// light client's header sync call should use, so the two can never
+/// disagree about what counts as a valid proof.
+pub fn verify_proof(proof: &Proof, verifier_list: &[Address]) -> bool {
+    if verifier_list.is_empty() {
+        return false;
+    }
+
+    let digest = proof_digest(proof);
+    let mut signed: HashSet<&Address> = HashSet::new();
+    for commit in &proof.commits {
+        if !verifier_list.contains(&commit.address) {
+            continue;
+        }
+        if recover_signer(&digest, &commit.signature).as_ref() != Some(&commit.address) {
+            continue;
+        }
+        signed.insert(&commit.address);
+    }
+
+    signed.len() * 3 > verifier_list.len() * 2
+}
+
+#[cfg(test)]
+mod tests {
+    use secp256k1::{PublicKey, SecretKey, SignOnly};
+
+    use core_types::Commit;
+
+    use super::*;
+
+    fn secret_key(byte: u8) -> SecretKey {
+        SecretKey::from_slice(&[byte; 32]).unwrap()
+    }
+
+    fn address_of(ctx: &Secp256k1<SignOnly>, sk: &SecretKey) -> Address {
+        let pubkey = PublicKey::from_secret_key(ctx, sk);
+        let address_hash = Hash::digest(&pubkey.serialize_uncompressed()[1..]);
+        Address::from_bytes(address_hash.as_bytes()[12..].to_vec())
+    }
+
+    fn commit(ctx: &Secp256k1<SignOnly>, sk: &SecretKey, proof: &Proof) -> Commit {
+        let digest = proof_digest(proof);
+        let message = Message::from_slice(digest.as_bytes()).unwrap();
+        let (recovery_id, sig) = ctx.sign_recoverable(&message, sk).serialize_compact();
+        let mut signature = sig.to_vec();
+        signature.push(recovery_id.to_i32() as u8);
+        Commit {
+            address: address_of(ctx, sk),
+            signature,
+        }
+    }
+
+    fn proof(commits: Vec<Commit>) -> Proof {
+        Proof {
+            height: 1,
+            round: 0,
+            block_hash: Hash::digest(b"block"),
+            commits,
+        }
+    }
+
+    #[test]
+    fn empty_verifier_list_never_verifies() {
+        assert!(!verify_proof(&proof(vec![]), &[]));
+    }
+
+    #[test]
+    fn unanimous_commits_reach_quorum() {
+        let ctx = Secp256k1::signing_only();
+        let keys: Vec<_> = (1..=3u8).map(secret_key).collect();
+        let verifier_list: Vec<_> = keys.iter().map(|sk| address_of(&ctx, sk)).collect();
+
+        let mut p = proof(vec![]);
+        p.commits = keys.iter().map(|sk| commit(&ctx, sk, &p)).collect();
+
+        assert!(verify_proof(&p, &verifier_list));
+    }
+
+    #[test]
+    fn half_of_four_verifiers_does_not_reach_quorum() {
+        // 3 of 4 is required for a 4-verifier set; 2 of 4 must fail.
+        let ctx = Secp256k1::signing_only();
+        let keys: Vec<_> = (1..=4u8).map(secret_key).collect();
+        let verifier_list: Vec<_> = keys.iter().map(|sk| address_of(&ctx, sk)).collect();
+
+        let mut p = proof(vec![]);
+        p.commits = keys[..2].iter().map(|sk| commit(&ctx, sk, &p)).collect();
+
+        assert!(!verify_proof(&p, &verifier_list));
+    }
+
+    #[test]
+    fn more_than_two_thirds_reaches_quorum() {
+        let ctx = Secp256k1::signing_only();
+        let keys: Vec<_> = (1..=4u8).map(secret_key).collect();
+        let verifier_list: Vec<_> = keys.iter().map(|sk| address_of(&ctx, sk)).collect();
+
+        let mut p = proof(vec![]);
+        p.commits = keys[..3].iter().map(|sk| commit(&ctx, sk, &p)).collect();
+
+        assert!(verify_proof(&p, &verifier_list));
+    }
+
+    #[test]
+    fn commit_from_outside_verifier_list_does_not_count() {
+        let ctx = Secp256k1::signing_only();
+        let keys: Vec<_> = (1..=4u8).map(secret_key).collect();
+        let verifier_list: Vec<_> = keys[..3].iter().map(|sk| address_of(&ctx, sk)).collect();
+
+        let mut p = proof(vec![]);
+        // Three commits, but one is from a key outside `verifier_list`.
+        p.commits = vec![
+            commit(&ctx, &keys[0], &p),
+            commit(&ctx, &keys[1], &p),
+            commit(&ctx, &keys[3], &p),
+        ];
+
+        assert!(!verify_proof(&p, &verifier_list));
+    }
+
+    #[test]
+    fn duplicate_commits_from_the_same_address_count_once() {
+        let ctx = Secp256k1::signing_only();
+        let keys: Vec<_> = (1..=4u8).map(secret_key).collect();
+        let verifier_list: Vec<_> = keys.iter().map(|sk| address_of(&ctx, sk)).collect();
+
+        let mut p = proof(vec![]);
+        let duplicate = commit(&ctx, &keys[0], &p);
+        p.commits = vec![duplicate.clone(), duplicate];
+
+        assert!(!verify_proof(&p, &verifier_list));
+    }
+
+    #[test]
+    fn signature_that_recovers_to_a_different_address_does_not_count() {
+        let ctx = Secp256k1::signing_only();
+        let keys: Vec<_> = (1..=3u8).map(secret_key).collect();
+        let verifier_list: Vec<_> = keys.iter().map(|sk| address_of(&ctx, sk)).collect();
+
+        let mut p = proof(vec![]);
+        // Sign with one key but claim to be a different verifier.
+        let mut forged = commit(&ctx, &keys[0], &p);
+        forged.address = address_of(&ctx, &keys[1]);
+        p.commits = vec![forged, commit(&ctx, &keys[1], &p), commit(&ctx, &keys[2], &p)];
+
+        assert!(!verify_proof(&p, &verifier_list));
+    }
+
+    #[test]
+    fn signature_over_a_different_block_hash_does_not_count() {
+        let ctx = Secp256k1::signing_only();
+        let keys: Vec<_> = (1..=3u8).map(secret_key).collect();
+        let verifier_list: Vec<_> = keys.iter().map(|sk| address_of(&ctx, sk)).collect();
+
+        let signed_proof = proof(vec![]);
+        let commits: Vec<_> = keys.iter().map(|sk| commit(&ctx, sk, &signed_proof)).collect();
+
+        // Same commits, but replayed against a proof for a different block.
+        let mut replayed = proof(vec![]);
+        replayed.block_hash = Hash::digest(b"a-different-block");
+        replayed.commits = commits;
+
+        assert!(!verify_proof(&replayed, &verifier_list));
+    }
+}
@@ -0,0 +1,12 @@
+/// Request-scoped context threaded through storage and RPC calls.
+///
+/// Currently a thin placeholder; it exists so call sites don't need to
+/// change signatures once request tracing/deadlines are added.
+#[derive(Debug, Clone, Default)]
+pub struct Context;
+
+impl Context {
+    pub fn new() -> Self {
+        Context
+    }
+}
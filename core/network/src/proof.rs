@@ -0,0 +1,75 @@
+use serde_derive::{Deserialize, Serialize};
+
+use components_executor::proof::build_proof;
+use components_executor::TrieDB;
+use core_runtime::Database;
+use core_types::Hash;
+
+/// Requests a Merkle proof for a single state key (an account address or a
+/// storage slot hash) as of `state_root`, so a light client can verify a
+/// query answer against a header it already trusts without storing state
+/// itself.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GetProofRequest {
+    pub state_root: Hash,
+    pub key_hash:   Hash,
+}
+
+/// `nodes` is the ordered, root-to-leaf chain of raw trie node encodings
+/// proving `key_hash`'s presence under `state_root`. There is no proof of
+/// absence: `serve_proof_request` returns an error for a key it can't find.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProofResponse {
+    pub nodes: Vec<Vec<u8>>,
+}
+
+#[derive(Debug)]
+pub enum ProofFetchError {
+    Network(String),
+    Trie(String),
+}
+
+/// Fetches a proof for a state key from a peer, keyed by this module's
+/// `GetProofRequest`/`ProofResponse` pair.
+pub trait ProofSource: Send + Sync {
+    fn request_proof(&self, request: GetProofRequest) -> Result<ProofResponse, ProofFetchError>;
+}
+
+/// The `ProofSource` light mode runs with. It is not connected to the
+/// reactor or to any peer: every `request_proof` call returns
+/// `ProofFetchError::Network`.
+pub struct NetworkProofSource;
+
+impl NetworkProofSource {
+    pub fn new() -> Self {
+        NetworkProofSource
+    }
+}
+
+impl Default for NetworkProofSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ProofSource for NetworkProofSource {
+    fn request_proof(&self, request: GetProofRequest) -> Result<ProofResponse, ProofFetchError> {
+        let _ = request;
+        Err(ProofFetchError::Network(
+            "no peer connection available to request a state proof".to_owned(),
+        ))
+    }
+}
+
+/// The server side of `ProofSource`: answers a peer's `GetProofRequest` by
+/// walking `trie` for the requested key under `request.state_root` and
+/// handing back the raw node chain `build_proof` finds, for the reactor's
+/// inbound request handler to wire up once it dispatches requests by type.
+pub fn serve_proof_request<DB: Database>(
+    trie: &TrieDB<DB>,
+    request: GetProofRequest,
+) -> Result<ProofResponse, ProofFetchError> {
+    let nodes = build_proof(trie, &request.state_root, &request.key_hash)
+        .map_err(|e| ProofFetchError::Trie(format!("{:?}", e)))?;
+    Ok(ProofResponse { nodes })
+}
@@ -0,0 +1,54 @@
+use core_runtime::Database;
+use core_storage::snapshot::{build_snapshot, ChunkSource, SnapshotError, SnapshotManifest, TrieReader};
+use core_types::{BlockHeader, Hash, Proof};
+
+/// The `ChunkSource` fast sync runs with, keyed by chunk hash — the sibling
+/// of `core_network::proof::NetworkProofSource` for bulk state transfer
+/// instead of single-key Merkle proofs. It is not connected to the reactor
+/// or to any peer: every `fetch_chunk` call returns `SnapshotError::Source`.
+pub struct NetworkChunkSource;
+
+impl NetworkChunkSource {
+    pub fn new() -> Self {
+        NetworkChunkSource
+    }
+}
+
+impl Default for NetworkChunkSource {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChunkSource for NetworkChunkSource {
+    fn fetch_chunk(&self, hash: &Hash) -> Result<Vec<u8>, SnapshotError> {
+        let _ = hash;
+        Err(SnapshotError::Source(
+            "no peer connection available to fetch a fast-sync chunk".to_owned(),
+        ))
+    }
+}
+
+/// Returns a fast-sync manifest together with the proof attesting to its
+/// header, the pair `restore_snapshot` needs to authenticate the manifest
+/// before trusting anything in it. It is not connected to any peer: it
+/// always returns `SnapshotError::Source`.
+pub fn fetch_manifest() -> Result<(SnapshotManifest, Proof), SnapshotError> {
+    Err(SnapshotError::Source(
+        "no peer connection available to fetch a fast-sync manifest".to_owned(),
+    ))
+}
+
+/// The server side of `fetch_manifest`: builds a fresh snapshot of `trie`
+/// as of `block_header` and pairs it with `proof` (the proof attesting to
+/// that same header), for the reactor's inbound request handler to serve
+/// once it dispatches requests by type.
+pub fn serve_manifest_request<R: TrieReader, DB: Database>(
+    trie: &R,
+    snapshot_db: &DB,
+    block_header: BlockHeader,
+    proof: Proof,
+) -> Result<(SnapshotManifest, Proof), SnapshotError> {
+    let manifest = build_snapshot(trie, snapshot_db, block_header)?;
+    Ok((manifest, proof))
+}
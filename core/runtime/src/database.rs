@@ -1,7 +1,13 @@
 use crate::FutRuntimeResult;
 
+/// A boxed, synchronous iterator over key/value pairs, returned by
+/// `Database::iter`. Unlike the rest of the trait it isn't wrapped in a
+/// `FutRuntimeResult`: backends that support it (RocksDB) hand back a
+/// native, blocking iterator rather than a future.
+pub type DbIterator = Box<dyn Iterator<Item = (Vec<u8>, Vec<u8>)> + Send>;
+
 /// Specify the category of data stored, and users can store the data in a decentralized manner.
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
 pub enum DataCategory {
     // Block
     Block,
@@ -13,6 +19,9 @@ pub enum DataCategory {
     State,
     // "SignedTransaction" in the transaction pool
     TransactionPool,
+    // Pruning journal entries and trie-node reference counts, plus other
+    // non-trie bookkeeping (fat-DB preimages, snapshot restore state).
+    Journal,
 }
 
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -20,6 +29,9 @@ pub enum DatabaseError {
     NotFound,
     InvalidData,
     Internal(String),
+    /// Returned by `iter` on backends that can't iterate by prefix at all
+    /// (currently everything but RocksDB).
+    Unsupported,
 }
 
 pub trait Database: Send + Sync {
@@ -54,4 +66,15 @@ pub trait Database: Send + Sync {
         c: DataCategory,
         keys: &[Vec<u8>],
     ) -> FutRuntimeResult<(), DatabaseError>;
+
+    /// Iterates all key/value pairs in `c` whose key starts with `prefix`.
+    /// `prefix` is an arbitrary, caller-chosen byte string (it may even be
+    /// empty, for a full dump of `c`), not a fixed-length key fragment, so
+    /// this seeks to `prefix` and scans forward until a key no longer
+    /// starts with it, rather than relying on a store's bloom-filter-
+    /// backed prefix extractor (those need a fixed extraction scheme
+    /// known up front, which a variable-length `prefix` argument doesn't
+    /// give it). Only the RocksDB backend supports this; other backends
+    /// return `DatabaseError::Unsupported`.
+    fn iter(&self, c: DataCategory, prefix: &[u8]) -> Result<DbIterator, DatabaseError>;
 }
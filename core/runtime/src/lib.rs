@@ -0,0 +1,9 @@
+use futures01::future::Future;
+
+pub mod database;
+
+pub use crate::database::{DataCategory, Database, DatabaseError, DbIterator};
+
+/// A boxed, `Send`-able `futures01` future, used throughout the workspace so
+/// that async-ish APIs don't need to name their concrete future type.
+pub type FutRuntimeResult<T, E> = Box<dyn Future<Item = T, Error = E> + Send>;
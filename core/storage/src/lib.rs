@@ -0,0 +1,124 @@
+use std::sync::Arc;
+
+use futures01::future::{self, Future};
+
+use core_runtime::{DataCategory, Database, DatabaseError, FutRuntimeResult};
+use core_types::{Block, Proof};
+
+pub mod pruning;
+pub mod snapshot;
+
+const LATEST_BLOCK_KEY: &[u8] = b"latest-block";
+const LATEST_PROOF_KEY: &[u8] = b"latest-proof";
+
+#[derive(Debug)]
+pub enum StorageError {
+    Database(DatabaseError),
+    Encode(String),
+}
+
+impl From<DatabaseError> for StorageError {
+    fn from(err: DatabaseError) -> Self {
+        StorageError::Database(err)
+    }
+}
+
+/// Persists blocks, headers and BFT proofs on top of a `Database`, keyed by
+/// height and by well-known "latest" keys.
+pub trait Storage: Send + Sync {
+    fn get_latest_block(&self, ctx: core_context::Context) -> FutRuntimeResult<Block, StorageError>;
+
+    fn get_block_by_height(
+        &self,
+        ctx: core_context::Context,
+        height: u64,
+    ) -> FutRuntimeResult<Block, StorageError>;
+
+    fn insert_block(&self, ctx: core_context::Context, block: Block) -> FutRuntimeResult<(), StorageError>;
+
+    fn get_latest_proof(&self, ctx: core_context::Context) -> FutRuntimeResult<Proof, StorageError>;
+
+    fn update_latest_proof(
+        &self,
+        ctx: core_context::Context,
+        proof: Proof,
+    ) -> FutRuntimeResult<(), StorageError>;
+}
+
+pub struct BlockStorage<DB> {
+    db: Arc<DB>,
+}
+
+impl<DB: Database> BlockStorage<DB> {
+    pub fn new(db: Arc<DB>) -> Self {
+        BlockStorage { db }
+    }
+
+    fn height_key(height: u64) -> Vec<u8> {
+        height.to_be_bytes().to_vec()
+    }
+}
+
+impl<DB: Database + 'static> Storage for BlockStorage<DB> {
+    fn get_latest_block(&self, _ctx: core_context::Context) -> FutRuntimeResult<Block, StorageError> {
+        let db = Arc::clone(&self.db);
+        let fut = future::lazy(move || {
+            let raw = db.get(DataCategory::Block, LATEST_BLOCK_KEY).wait()?;
+            let block: Block = serde_json::from_slice(&raw).map_err(|e| StorageError::Encode(e.to_string()))?;
+            Ok(block)
+        });
+        Box::new(fut)
+    }
+
+    fn get_block_by_height(
+        &self,
+        _ctx: core_context::Context,
+        height: u64,
+    ) -> FutRuntimeResult<Block, StorageError> {
+        let db = Arc::clone(&self.db);
+        let fut = future::lazy(move || {
+            let raw = db
+                .get(DataCategory::Block, &Self::height_key(height))
+                .wait()?;
+            let block: Block = serde_json::from_slice(&raw).map_err(|e| StorageError::Encode(e.to_string()))?;
+            Ok(block)
+        });
+        Box::new(fut)
+    }
+
+    fn insert_block(&self, _ctx: core_context::Context, block: Block) -> FutRuntimeResult<(), StorageError> {
+        let db = Arc::clone(&self.db);
+        let fut = future::lazy(move || {
+            let raw = serde_json::to_vec(&block).map_err(|e| StorageError::Encode(e.to_string()))?;
+            db.insert(DataCategory::Block, &Self::height_key(block.header.height), &raw)
+                .wait()?;
+            db.insert(DataCategory::Block, LATEST_BLOCK_KEY, &raw).wait()?;
+            Ok(())
+        });
+        Box::new(fut)
+    }
+
+    fn get_latest_proof(&self, _ctx: core_context::Context) -> FutRuntimeResult<Proof, StorageError> {
+        let db = Arc::clone(&self.db);
+        let fut = future::lazy(move || {
+            let raw = db.get(DataCategory::Block, LATEST_PROOF_KEY).wait()?;
+            let proof: Proof = serde_json::from_slice(&raw).map_err(|e| StorageError::Encode(e.to_string()))?;
+            Ok(proof)
+        });
+        Box::new(fut)
+    }
+
+    fn update_latest_proof(
+        &self,
+        _ctx: core_context::Context,
+        proof: Proof,
+    ) -> FutRuntimeResult<(), StorageError> {
+        let db = Arc::clone(&self.db);
+        let fut = future::lazy(move || {
+            let raw = serde_json::to_vec(&proof).map_err(|e| StorageError::Encode(e.to_string()))?;
+            db.insert(DataCategory::Block, LATEST_PROOF_KEY, &raw).wait()?;
+            Ok(())
+        });
+        Box::new(fut)
+    }
+}
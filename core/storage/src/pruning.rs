@@ -0,0 +1,294 @@
+use std::convert::TryInto;
+use std::sync::Arc;
+
+use futures01::future::Future;
+use serde_derive::{Deserialize, Serialize};
+
+use core_runtime::{DataCategory, Database, DatabaseError};
+use core_types::Hash;
+
+const REFCOUNT_KEY_PREFIX: &[u8] = b"refcount:";
+
+#[derive(Debug)]
+pub enum PruningError {
+    Database(DatabaseError),
+    Encode(String),
+}
+
+impl From<DatabaseError> for PruningError {
+    fn from(err: DatabaseError) -> Self {
+        PruningError::Database(err)
+    }
+}
+
+/// Archive never deletes anything; Fast keeps per-block journals and
+/// refcounts so `prune_at` can delete nodes whose refcount reaches zero.
+/// `history` is the configured window; `PruningJournal` stores it but does
+/// not apply it itself: the caller of `prune_at` picks which heights to
+/// prune.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PruningMode {
+    Archive,
+    Fast { history: u64 },
+}
+
+/// One block's contribution to the pruning journal: the trie nodes it
+/// newly inserted, and the trie nodes its commit made unreferenced (no
+/// longer reachable from the new state root, but possibly still kept
+/// alive by an older block that hasn't been pruned yet).
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct JournalEntry {
+    pub inserted:     Vec<Hash>,
+    pub unreferenced: Vec<Hash>,
+}
+
+/// Reference-counted pruning over the state trie, journaled per block so a
+/// reorg can roll back the refcount changes of the branch it discards
+/// before pruning proceeds on the new canonical chain.
+pub struct PruningJournal<DB> {
+    db:   Arc<DB>,
+    mode: PruningMode,
+}
+
+impl<DB: Database> PruningJournal<DB> {
+    pub fn new(db: Arc<DB>, mode: PruningMode) -> Self {
+        PruningJournal { db, mode }
+    }
+
+    pub fn mode(&self) -> PruningMode {
+        self.mode
+    }
+
+    fn journal_key(height: u64, block_hash: &Hash) -> Vec<u8> {
+        let mut key = height.to_be_bytes().to_vec();
+        key.extend_from_slice(block_hash.as_bytes());
+        key
+    }
+
+    fn refcount_key(hash: &Hash) -> Vec<u8> {
+        let mut key = REFCOUNT_KEY_PREFIX.to_vec();
+        key.extend_from_slice(hash.as_bytes());
+        key
+    }
+
+    fn get_refcount(&self, hash: &Hash) -> Result<u64, PruningError> {
+        match self.db.get(DataCategory::Journal, &Self::refcount_key(hash)).wait() {
+            Ok(raw) => {
+                let bytes: [u8; 8] = raw
+                    .as_slice()
+                    .try_into()
+                    .map_err(|_| PruningError::Encode("corrupt refcount".to_owned()))?;
+                Ok(u64::from_be_bytes(bytes))
+            }
+            Err(DatabaseError::NotFound) => Ok(0),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    fn set_refcount(&self, hash: &Hash, count: u64) -> Result<(), PruningError> {
+        if count == 0 {
+            self.db.remove(DataCategory::Journal, &Self::refcount_key(hash)).wait()?;
+        } else {
+            self.db
+                .insert(DataCategory::Journal, &Self::refcount_key(hash), &count.to_be_bytes())
+                .wait()?;
+        }
+        Ok(())
+    }
+
+    /// Records `entry` as the journal for `(height, block_hash)` and bumps
+    /// the refcount of every node it inserted. No-op in `Archive` mode.
+    pub fn commit_block(&self, height: u64, block_hash: &Hash, entry: &JournalEntry) -> Result<(), PruningError> {
+        if self.mode == PruningMode::Archive {
+            return Ok(());
+        }
+
+        for hash in &entry.inserted {
+            let count = self.get_refcount(hash)?;
+            self.set_refcount(hash, count + 1)?;
+        }
+
+        let raw = serde_json::to_vec(entry).map_err(|e| PruningError::Encode(e.to_string()))?;
+        self.db
+            .insert(DataCategory::Journal, &Self::journal_key(height, block_hash), &raw)
+            .wait()?;
+        Ok(())
+    }
+
+    /// Applies the journal recorded for `(height, block_hash)`: every node
+    /// it made unreferenced has its refcount dropped, and is deleted from
+    /// `DataCategory::State` once that refcount reaches zero. The caller
+    /// is responsible for only pruning heights that both lie on the
+    /// canonical chain and are older than the configured `pruning_history`
+    /// window. No-op in `Archive` mode or if no journal was recorded for
+    /// this height (already pruned, or genesis).
+    pub fn prune_at(&self, height: u64, block_hash: &Hash) -> Result<(), PruningError> {
+        if self.mode == PruningMode::Archive {
+            return Ok(());
+        }
+
+        let key = Self::journal_key(height, block_hash);
+        let entry = match self.load_entry(&key)? {
+            Some(entry) => entry,
+            None => return Ok(()),
+        };
+
+        for hash in &entry.unreferenced {
+            let remaining = self.get_refcount(hash)?.saturating_sub(1);
+            self.set_refcount(hash, remaining)?;
+            if remaining == 0 {
+                self.db.remove(DataCategory::State, hash.as_bytes()).wait()?;
+            }
+        }
+
+        self.db.remove(DataCategory::Journal, &key).wait()?;
+        Ok(())
+    }
+
+    /// Reverts the refcount bumps `commit_block` made for `(height,
+    /// block_hash)`, without deleting anything. Used to unwind the
+    /// journals of a branch a reorg is discarding, so a still-referenced
+    /// node on the new canonical branch is never mistakenly pruned.
+    pub fn rollback_block(&self, height: u64, block_hash: &Hash) -> Result<(), PruningError> {
+        if self.mode == PruningMode::Archive {
+            return Ok(());
+        }
+
+        let key = Self::journal_key(height, block_hash);
+        let entry = match self.load_entry(&key)? {
+            Some(entry) => entry,
+            None => return Ok(()),
+        };
+
+        for hash in &entry.inserted {
+            let count = self.get_refcount(hash)?;
+            self.set_refcount(hash, count.saturating_sub(1))?;
+        }
+
+        self.db.remove(DataCategory::Journal, &key).wait()?;
+        Ok(())
+    }
+
+    fn load_entry(&self, key: &[u8]) -> Result<Option<JournalEntry>, PruningError> {
+        match self.db.get(DataCategory::Journal, key).wait() {
+            Ok(raw) => serde_json::from_slice(&raw)
+                .map(Some)
+                .map_err(|e| PruningError::Encode(e.to_string())),
+            Err(DatabaseError::NotFound) => Ok(None),
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use components_database::memory::MemoryDB;
+
+    use super::*;
+
+    fn journal(inserted: Vec<Hash>, unreferenced: Vec<Hash>) -> JournalEntry {
+        JournalEntry {
+            inserted,
+            unreferenced,
+        }
+    }
+
+    #[test]
+    fn archive_mode_never_touches_refcounts_or_state() {
+        let db = Arc::new(MemoryDB::new());
+        let journal_db = PruningJournal::new(Arc::clone(&db), PruningMode::Archive);
+        let hash = Hash::digest(b"node");
+        let block_hash = Hash::digest(b"block");
+        db.insert(DataCategory::State, hash.as_bytes(), b"node-bytes")
+            .wait()
+            .unwrap();
+
+        journal_db
+            .commit_block(1, &block_hash, &journal(vec![hash.clone()], vec![]))
+            .unwrap();
+        journal_db.prune_at(1, &block_hash).unwrap();
+        journal_db.rollback_block(1, &block_hash).unwrap();
+
+        assert_eq!(journal_db.get_refcount(&hash).unwrap(), 0);
+        assert!(db.contains(DataCategory::State, hash.as_bytes()).wait().unwrap());
+    }
+
+    #[test]
+    fn commit_block_accumulates_refcount_across_blocks() {
+        let db = Arc::new(MemoryDB::new());
+        let journal_db = PruningJournal::new(Arc::clone(&db), PruningMode::Fast { history: 0 });
+        let hash = Hash::digest(b"shared-node");
+
+        journal_db
+            .commit_block(1, &Hash::digest(b"block-1"), &journal(vec![hash.clone()], vec![]))
+            .unwrap();
+        journal_db
+            .commit_block(2, &Hash::digest(b"block-2"), &journal(vec![hash.clone()], vec![]))
+            .unwrap();
+
+        assert_eq!(journal_db.get_refcount(&hash).unwrap(), 2);
+    }
+
+    #[test]
+    fn prune_at_deletes_node_only_once_refcount_reaches_zero() {
+        let db = Arc::new(MemoryDB::new());
+        let journal_db = PruningJournal::new(Arc::clone(&db), PruningMode::Fast { history: 0 });
+        let hash = Hash::digest(b"node");
+        db.insert(DataCategory::State, hash.as_bytes(), b"node-bytes")
+            .wait()
+            .unwrap();
+
+        // Two blocks reference the node; only unreferencing it twice drops
+        // the refcount to zero and actually deletes it.
+        journal_db
+            .commit_block(1, &Hash::digest(b"block-1"), &journal(vec![hash.clone()], vec![]))
+            .unwrap();
+        journal_db
+            .commit_block(2, &Hash::digest(b"block-2"), &journal(vec![hash.clone()], vec![]))
+            .unwrap();
+
+        journal_db
+            .commit_block(3, &Hash::digest(b"block-3"), &journal(vec![], vec![hash.clone()]))
+            .unwrap();
+        journal_db.prune_at(3, &Hash::digest(b"block-3")).unwrap();
+        assert_eq!(journal_db.get_refcount(&hash).unwrap(), 1);
+        assert!(db.contains(DataCategory::State, hash.as_bytes()).wait().unwrap());
+
+        journal_db
+            .commit_block(4, &Hash::digest(b"block-4"), &journal(vec![], vec![hash.clone()]))
+            .unwrap();
+        journal_db.prune_at(4, &Hash::digest(b"block-4")).unwrap();
+        assert_eq!(journal_db.get_refcount(&hash).unwrap(), 0);
+        assert!(!db.contains(DataCategory::State, hash.as_bytes()).wait().unwrap());
+    }
+
+    #[test]
+    fn rollback_block_undoes_commit_without_touching_state() {
+        let db = Arc::new(MemoryDB::new());
+        let journal_db = PruningJournal::new(Arc::clone(&db), PruningMode::Fast { history: 0 });
+        let hash = Hash::digest(b"node");
+        let block_hash = Hash::digest(b"block");
+        db.insert(DataCategory::State, hash.as_bytes(), b"node-bytes")
+            .wait()
+            .unwrap();
+
+        journal_db
+            .commit_block(1, &block_hash, &journal(vec![hash.clone()], vec![]))
+            .unwrap();
+        assert_eq!(journal_db.get_refcount(&hash).unwrap(), 1);
+
+        journal_db.rollback_block(1, &block_hash).unwrap();
+
+        assert_eq!(journal_db.get_refcount(&hash).unwrap(), 0);
+        assert!(db.contains(DataCategory::State, hash.as_bytes()).wait().unwrap());
+    }
+
+    #[test]
+    fn prune_at_is_a_no_op_when_no_journal_was_recorded() {
+        let db = Arc::new(MemoryDB::new());
+        let journal_db = PruningJournal::new(Arc::clone(&db), PruningMode::Fast { history: 0 });
+
+        // Genesis / already-pruned height: nothing to do, no error.
+        journal_db.prune_at(0, &Hash::digest(b"genesis")).unwrap();
+    }
+}
@@ -0,0 +1,550 @@
+use std::collections::HashSet;
+
+use futures01::future::Future;
+use serde_derive::{Deserialize, Serialize};
+
+use core_runtime::{DataCategory, Database, DatabaseError};
+use core_types::{Address, BlockHeader, Hash, Proof};
+
+/// Trie nodes are batched into chunks of at most this many entries before
+/// being flushed and hashed.
+const MAX_CHUNK_NODES: usize = 4096;
+
+// Bookkeeping keys, stored under `DataCategory::Journal` (not `State`) so
+// that prefix-scanning `State` for a full state dump never turns up
+// anything but actual trie nodes.
+const BLACKLIST_KEY: &[u8] = b"snapshot-blacklist";
+const PENDING_KEY_PREFIX: &[u8] = b"snapshot-pending:";
+
+#[derive(Debug)]
+pub enum SnapshotError {
+    Database(DatabaseError),
+    Encode(String),
+    ChunkHashMismatch { expected: Hash, got: Hash },
+    /// An individual node pulled out of an otherwise correctly-hashed
+    /// chunk didn't hash to the key it was stored under.
+    NodeHashMismatch { expected: Hash, got: Hash },
+    StateRootUnreachable(Hash),
+    Blacklisted(Hash),
+    /// `manifest.block_header.hash()` didn't match the trusted `Proof`'s
+    /// `block_hash`, so the manifest can't be attributed to that proof.
+    HeaderMismatch,
+    /// The trusted `Proof` passed to `restore_snapshot` didn't verify
+    /// against `verifier_list`.
+    UnauthenticatedHeader,
+    /// A `ChunkSource` (or manifest source) failed to fetch its data.
+    Source(String),
+}
+
+impl From<DatabaseError> for SnapshotError {
+    fn from(err: DatabaseError) -> Self {
+        SnapshotError::Database(err)
+    }
+}
+
+/// Read access to the state trie, abstracted away from its node encoding so
+/// this module doesn't need to depend on `components_executor`'s `TrieDB`.
+/// `components_executor` implements this for `TrieDB`.
+pub trait TrieReader {
+    /// Returns a node's raw encoded bytes together with the hashes of any
+    /// children it references.
+    fn read_node(&self, hash: &Hash) -> Result<(Vec<u8>, Vec<Hash>), SnapshotError>;
+
+    /// True if a node with this hash is present.
+    fn has_node(&self, hash: &Hash) -> Result<bool, SnapshotError>;
+}
+
+/// Fetches a chunk's raw bytes given its advertised hash. A joining node
+/// implements this over `core_network`, asking the peer that served the
+/// manifest for each chunk in turn.
+pub trait ChunkSource {
+    fn fetch_chunk(&self, hash: &Hash) -> Result<Vec<u8>, SnapshotError>;
+}
+
+/// Describes one state snapshot: the block it was taken at, the state root
+/// it reconstructs, and the ordered list of chunk hashes that make it up.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SnapshotManifest {
+    pub block_header: BlockHeader,
+    pub state_root:   Hash,
+    pub chunk_hashes: Vec<Hash>,
+}
+
+impl SnapshotManifest {
+    pub fn manifest_hash(&self) -> Result<Hash, SnapshotError> {
+        let raw = serde_json::to_vec(self).map_err(|e| SnapshotError::Encode(e.to_string()))?;
+        Ok(Hash::digest(&raw))
+    }
+}
+
+/// Walks the state trie rooted at `block_header.state_root`, grouping the
+/// reachable nodes into chunks of up to `MAX_CHUNK_NODES` and writing each
+/// chunk into `snapshot_db` under `DataCategory::State`, keyed by the hash
+/// of the chunk's own encoding.
+pub fn build_snapshot<R: TrieReader, DB: Database>(
+    trie: &R,
+    snapshot_db: &DB,
+    block_header: BlockHeader,
+) -> Result<SnapshotManifest, SnapshotError> {
+    let mut visited = HashSet::new();
+    let mut queue = vec![block_header.state_root.clone()];
+    let mut batch: Vec<(Hash, Vec<u8>)> = Vec::new();
+    let mut chunk_hashes = Vec::new();
+
+    while let Some(hash) = queue.pop() {
+        if !visited.insert(hash.clone()) {
+            continue;
+        }
+        let (raw_node, children) = trie.read_node(&hash)?;
+        queue.extend(children);
+        batch.push((hash, raw_node));
+
+        if batch.len() >= MAX_CHUNK_NODES {
+            chunk_hashes.push(flush_chunk(snapshot_db, &mut batch)?);
+        }
+    }
+    if !batch.is_empty() {
+        chunk_hashes.push(flush_chunk(snapshot_db, &mut batch)?);
+    }
+
+    Ok(SnapshotManifest {
+        state_root: block_header.state_root.clone(),
+        block_header,
+        chunk_hashes,
+    })
+}
+
+fn flush_chunk<DB: Database>(db: &DB, batch: &mut Vec<(Hash, Vec<u8>)>) -> Result<Hash, SnapshotError> {
+    let nodes = std::mem::replace(batch, Vec::new());
+    let raw = serde_json::to_vec(&nodes).map_err(|e| SnapshotError::Encode(e.to_string()))?;
+    let chunk_hash = Hash::digest(&raw);
+    db.insert(DataCategory::State, chunk_hash.as_bytes(), &raw).wait()?;
+    Ok(chunk_hash)
+}
+
+/// Restores state from `manifest` into `snapshot_db`. `manifest.block_header`
+/// is only trusted once it's shown to be the header `proof` actually
+/// attests to — `proof.block_hash` must match the header's own hash, and
+/// `proof` itself must verify against `verifier_list` (see
+/// `core_consensus::verify_proof`) — otherwise a manifest provider could
+/// hand a joining node an entirely self-consistent but fabricated state
+/// tree. Each chunk is fetched from `source`, checked against its
+/// advertised hash, then every node decoded out of it is re-hashed and
+/// checked against the key it's stored under, before the result is
+/// checked against `trie` for reachability from `manifest.state_root`. A
+/// chunk is dropped from the persisted pending set only once its nodes
+/// are written, so a restore interrupted midway resumes from the first
+/// unimported chunk rather than starting over.
+pub fn restore_snapshot<R: TrieReader, DB: Database>(
+    trie: &R,
+    snapshot_db: &DB,
+    manifest: &SnapshotManifest,
+    proof: &Proof,
+    verifier_list: &[Address],
+    source: &dyn ChunkSource,
+) -> Result<(), SnapshotError> {
+    if manifest.block_header.hash() != proof.block_hash {
+        return Err(SnapshotError::HeaderMismatch);
+    }
+    if !core_consensus::verify_proof(proof, verifier_list) {
+        return Err(SnapshotError::UnauthenticatedHeader);
+    }
+
+    let manifest_hash = manifest.manifest_hash()?;
+    let blacklist = SnapshotBlacklist::new(snapshot_db);
+    if blacklist.contains(&manifest_hash)? {
+        return Err(SnapshotError::Blacklisted(manifest_hash));
+    }
+
+    let pending_key = pending_key(&manifest_hash);
+    let mut pending = load_pending(snapshot_db, &pending_key)?.unwrap_or_else(|| manifest.chunk_hashes.clone());
+
+    while let Some(chunk_hash) = pending.first().cloned() {
+        if let Err(err) = import_chunk(snapshot_db, source, &chunk_hash) {
+            // Only bad chunk data marks the snapshot as bad; a fetch or
+            // database failure leaves it resumable from the pending set.
+            match err {
+                SnapshotError::ChunkHashMismatch { .. }
+                | SnapshotError::NodeHashMismatch { .. }
+                | SnapshotError::Encode(_) => blacklist.add(manifest_hash)?,
+                _ => {}
+            }
+            return Err(err);
+        }
+        pending.remove(0);
+        persist_pending(snapshot_db, &pending_key, &pending)?;
+    }
+
+    if verify_reachable(trie, &manifest.state_root).is_err() {
+        blacklist.add(manifest_hash)?;
+        return Err(SnapshotError::StateRootUnreachable(manifest.state_root.clone()));
+    }
+
+    snapshot_db.remove(DataCategory::Journal, &pending_key).wait()?;
+    Ok(())
+}
+
+fn import_chunk<DB: Database>(db: &DB, source: &dyn ChunkSource, chunk_hash: &Hash) -> Result<(), SnapshotError> {
+    let raw = source.fetch_chunk(chunk_hash)?;
+    let got_hash = Hash::digest(&raw);
+    if &got_hash != chunk_hash {
+        return Err(SnapshotError::ChunkHashMismatch {
+            expected: chunk_hash.clone(),
+            got:      got_hash,
+        });
+    }
+
+    let nodes: Vec<(Hash, Vec<u8>)> =
+        serde_json::from_slice(&raw).map_err(|e| SnapshotError::Encode(e.to_string()))?;
+    let mut keys = Vec::with_capacity(nodes.len());
+    let mut values = Vec::with_capacity(nodes.len());
+    for (hash, bytes) in nodes {
+        let got_hash = Hash::digest(&bytes);
+        if got_hash != hash {
+            return Err(SnapshotError::NodeHashMismatch {
+                expected: hash,
+                got:      got_hash,
+            });
+        }
+        keys.push(hash.as_bytes().to_vec());
+        values.push(bytes);
+    }
+    db.insert_batch(DataCategory::State, &keys, &values).wait()?;
+    Ok(())
+}
+
+fn verify_reachable<R: TrieReader>(trie: &R, root: &Hash) -> Result<(), SnapshotError> {
+    let mut visited = HashSet::new();
+    let mut queue = vec![root.clone()];
+    while let Some(hash) = queue.pop() {
+        if !visited.insert(hash.clone()) {
+            continue;
+        }
+        if !trie.has_node(&hash)? {
+            return Err(SnapshotError::StateRootUnreachable(hash));
+        }
+        let (_, children) = trie.read_node(&hash)?;
+        queue.extend(children);
+    }
+    Ok(())
+}
+
+fn pending_key(manifest_hash: &Hash) -> Vec<u8> {
+    let mut key = PENDING_KEY_PREFIX.to_vec();
+    key.extend_from_slice(manifest_hash.as_bytes());
+    key
+}
+
+fn load_pending<DB: Database>(db: &DB, key: &[u8]) -> Result<Option<Vec<Hash>>, SnapshotError> {
+    match db.get(DataCategory::Journal, key).wait() {
+        Ok(raw) => serde_json::from_slice(&raw)
+            .map(Some)
+            .map_err(|e| SnapshotError::Encode(e.to_string())),
+        Err(DatabaseError::NotFound) => Ok(None),
+        Err(err) => Err(err.into()),
+    }
+}
+
+fn persist_pending<DB: Database>(db: &DB, key: &[u8], pending: &[Hash]) -> Result<(), SnapshotError> {
+    let raw = serde_json::to_vec(pending).map_err(|e| SnapshotError::Encode(e.to_string()))?;
+    db.insert(DataCategory::Journal, key, &raw).wait()?;
+    Ok(())
+}
+
+/// Persisted set of manifest hashes whose restore previously failed
+/// verification, so a node doesn't keep retrying a snapshot known to be bad.
+pub struct SnapshotBlacklist<'a, DB> {
+    db: &'a DB,
+}
+
+impl<'a, DB: Database> SnapshotBlacklist<'a, DB> {
+    pub fn new(db: &'a DB) -> Self {
+        SnapshotBlacklist { db }
+    }
+
+    fn load(&self) -> Result<HashSet<Hash>, SnapshotError> {
+        match self.db.get(DataCategory::Journal, BLACKLIST_KEY).wait() {
+            Ok(raw) => serde_json::from_slice(&raw).map_err(|e| SnapshotError::Encode(e.to_string())),
+            Err(DatabaseError::NotFound) => Ok(HashSet::new()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    pub fn contains(&self, manifest_hash: &Hash) -> Result<bool, SnapshotError> {
+        Ok(self.load()?.contains(manifest_hash))
+    }
+
+    pub fn add(&self, manifest_hash: Hash) -> Result<(), SnapshotError> {
+        let mut set = self.load()?;
+        set.insert(manifest_hash);
+        let raw = serde_json::to_vec(&set).map_err(|e| SnapshotError::Encode(e.to_string()))?;
+        self.db.insert(DataCategory::Journal, BLACKLIST_KEY, &raw).wait()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use components_database::memory::MemoryDB;
+    use core_types::Commit;
+
+    use super::*;
+
+    struct StaticChunkSource {
+        chunks: std::collections::HashMap<Hash, Vec<u8>>,
+    }
+
+    impl ChunkSource for StaticChunkSource {
+        fn fetch_chunk(&self, hash: &Hash) -> Result<Vec<u8>, SnapshotError> {
+            self.chunks
+                .get(hash)
+                .cloned()
+                .ok_or_else(|| SnapshotError::Source("no such chunk".to_owned()))
+        }
+    }
+
+    fn chunk_of(nodes: Vec<(Hash, Vec<u8>)>) -> (Hash, Vec<u8>) {
+        let raw = serde_json::to_vec(&nodes).unwrap();
+        (Hash::digest(&raw), raw)
+    }
+
+    #[test]
+    fn import_chunk_writes_hash_verified_nodes() {
+        let db = MemoryDB::new();
+        let leaf = b"leaf-data".to_vec();
+        let leaf_hash = Hash::digest(&leaf);
+        let (chunk_hash, chunk_raw) = chunk_of(vec![(leaf_hash.clone(), leaf.clone())]);
+        let source = StaticChunkSource {
+            chunks: std::iter::once((chunk_hash.clone(), chunk_raw)).collect(),
+        };
+
+        import_chunk(&db, &source, &chunk_hash).unwrap();
+
+        let stored = db.get(DataCategory::State, leaf_hash.as_bytes()).wait().unwrap();
+        assert_eq!(stored, leaf);
+    }
+
+    #[test]
+    fn import_chunk_rejects_tampered_chunk_bytes() {
+        let db = MemoryDB::new();
+        let (chunk_hash, chunk_raw) = chunk_of(vec![(Hash::digest(b"a"), b"a".to_vec())]);
+        let mut tampered = chunk_raw;
+        tampered.push(0xff);
+        let source = StaticChunkSource {
+            chunks: std::iter::once((chunk_hash.clone(), tampered)).collect(),
+        };
+
+        let err = import_chunk(&db, &source, &chunk_hash).unwrap_err();
+        assert!(matches!(err, SnapshotError::ChunkHashMismatch { .. }));
+    }
+
+    #[test]
+    fn import_chunk_rejects_node_not_matching_its_own_key() {
+        let db = MemoryDB::new();
+        // The chunk blob itself hashes correctly, but the node inside it
+        // claims a hash that doesn't match its bytes.
+        let wrong_hash = Hash::digest(b"not-the-real-preimage");
+        let (chunk_hash, chunk_raw) = chunk_of(vec![(wrong_hash.clone(), b"actual-bytes".to_vec())]);
+        let source = StaticChunkSource {
+            chunks: std::iter::once((chunk_hash.clone(), chunk_raw)).collect(),
+        };
+
+        let err = import_chunk(&db, &source, &chunk_hash).unwrap_err();
+        assert!(matches!(err, SnapshotError::NodeHashMismatch { expected, .. } if expected == wrong_hash));
+    }
+
+    #[test]
+    fn pending_set_round_trips_and_shrinks_as_chunks_are_consumed() {
+        let db = MemoryDB::new();
+        let key = pending_key(&Hash::digest(b"manifest"));
+        let chunks = vec![Hash::digest(b"a"), Hash::digest(b"b")];
+
+        assert!(load_pending(&db, &key).unwrap().is_none());
+
+        persist_pending(&db, &key, &chunks).unwrap();
+        assert_eq!(load_pending(&db, &key).unwrap(), Some(chunks.clone()));
+
+        persist_pending(&db, &key, &chunks[1..]).unwrap();
+        assert_eq!(load_pending(&db, &key).unwrap(), Some(vec![chunks[1].clone()]));
+    }
+
+    #[test]
+    fn restore_snapshot_rejects_header_not_attested_by_proof() {
+        let db = MemoryDB::new();
+        let manifest = SnapshotManifest {
+            block_header: BlockHeader::default(),
+            state_root:   Hash::digest(b"root"),
+            chunk_hashes: Vec::new(),
+        };
+        let proof = Proof {
+            block_hash: Hash::digest(b"some-other-header"),
+            ..Default::default()
+        };
+        let source = StaticChunkSource {
+            chunks: std::collections::HashMap::new(),
+        };
+
+        struct EmptyTrie;
+        impl TrieReader for EmptyTrie {
+            fn read_node(&self, _hash: &Hash) -> Result<(Vec<u8>, Vec<Hash>), SnapshotError> {
+                unreachable!("header check must fail before any trie access")
+            }
+            fn has_node(&self, _hash: &Hash) -> Result<bool, SnapshotError> {
+                unreachable!("header check must fail before any trie access")
+            }
+        }
+
+        let err = restore_snapshot(&EmptyTrie, &db, &manifest, &proof, &[], &source).unwrap_err();
+        assert!(matches!(err, SnapshotError::HeaderMismatch));
+    }
+
+    /// A `TrieReader` backed directly by a `Database`'s `DataCategory::
+    /// State`, where each node's raw encoding is `(payload, children)` —
+    /// just enough structure for `build_snapshot`/`restore_snapshot`'s walk
+    /// to exercise real node traversal without depending on
+    /// `components_executor`'s actual `TrieNode` encoding.
+    struct DbTrie<'a, DB> {
+        db: &'a DB,
+    }
+
+    impl<'a, DB: Database> TrieReader for DbTrie<'a, DB> {
+        fn read_node(&self, hash: &Hash) -> Result<(Vec<u8>, Vec<Hash>), SnapshotError> {
+            let raw = self.db.get(DataCategory::State, hash.as_bytes()).wait()?;
+            let (_, children): (Vec<u8>, Vec<Hash>) =
+                serde_json::from_slice(&raw).map_err(|e| SnapshotError::Encode(e.to_string()))?;
+            Ok((raw, children))
+        }
+
+        fn has_node(&self, hash: &Hash) -> Result<bool, SnapshotError> {
+            Ok(self.db.contains(DataCategory::State, hash.as_bytes()).wait()?)
+        }
+    }
+
+    fn insert_node<DB: Database>(db: &DB, payload: Vec<u8>, children: Vec<Hash>) -> Hash {
+        let raw = serde_json::to_vec(&(payload, children)).unwrap();
+        let hash = Hash::digest(&raw);
+        db.insert(DataCategory::State, hash.as_bytes(), &raw).wait().unwrap();
+        hash
+    }
+
+    struct DbChunkSource<'a, DB> {
+        db: &'a DB,
+    }
+
+    impl<'a, DB: Database> ChunkSource for DbChunkSource<'a, DB> {
+        fn fetch_chunk(&self, hash: &Hash) -> Result<Vec<u8>, SnapshotError> {
+            Ok(self.db.get(DataCategory::State, hash.as_bytes()).wait()?)
+        }
+    }
+
+    /// Mirrors `core_consensus::proof_digest` (private to that crate) so
+    /// this test can produce a `Proof` that actually reaches quorum,
+    /// exercising `restore_snapshot`'s authentication the same way a real
+    /// caller's proof would.
+    fn sign_proof(proof: &mut Proof, secret_keys: &[secp256k1::SecretKey]) {
+        let mut preimage = proof.height.to_be_bytes().to_vec();
+        preimage.extend_from_slice(&proof.round.to_be_bytes());
+        preimage.extend_from_slice(proof.block_hash.as_bytes());
+        let digest = Hash::digest(&preimage);
+        let message = secp256k1::Message::from_slice(digest.as_bytes()).unwrap();
+
+        let ctx: secp256k1::Secp256k1<secp256k1::SignOnly> = secp256k1::Secp256k1::signing_only();
+        proof.commits = secret_keys
+            .iter()
+            .map(|sk| {
+                let pubkey = secp256k1::PublicKey::from_secret_key(&ctx, sk);
+                let address_hash = Hash::digest(&pubkey.serialize_uncompressed()[1..]);
+                let address = Address::from_bytes(address_hash.as_bytes()[12..].to_vec());
+
+                let (recovery_id, sig) = ctx.sign_recoverable(&message, sk).serialize_compact();
+                let mut signature = sig.to_vec();
+                signature.push(recovery_id.to_i32() as u8);
+                Commit { address, signature }
+            })
+            .collect();
+    }
+
+    #[test]
+    fn build_snapshot_then_restore_snapshot_round_trips_the_whole_trie() {
+        let source_db = MemoryDB::new();
+        let leaf_a = insert_node(&source_db, b"leaf-a".to_vec(), vec![]);
+        let leaf_b = insert_node(&source_db, b"leaf-b".to_vec(), vec![]);
+        let root = insert_node(&source_db, Vec::new(), vec![leaf_a, leaf_b]);
+
+        let header = BlockHeader {
+            state_root: root.clone(),
+            ..Default::default()
+        };
+
+        let snapshot_db = MemoryDB::new();
+        let manifest = build_snapshot(&DbTrie { db: &source_db }, &snapshot_db, header.clone()).unwrap();
+        assert_eq!(manifest.state_root, root);
+        assert_eq!(manifest.chunk_hashes.len(), 1);
+
+        let mut proof = Proof {
+            height:    0,
+            round:     0,
+            block_hash: header.hash(),
+            commits:   Vec::new(),
+        };
+        let secret_keys: Vec<_> = (1..=3u8).map(|b| secp256k1::SecretKey::from_slice(&[b; 32]).unwrap()).collect();
+        sign_proof(&mut proof, &secret_keys);
+        let ctx: secp256k1::Secp256k1<secp256k1::SignOnly> = secp256k1::Secp256k1::signing_only();
+        let verifier_list: Vec<_> = secret_keys
+            .iter()
+            .map(|sk| {
+                let pubkey = secp256k1::PublicKey::from_secret_key(&ctx, sk);
+                let address_hash = Hash::digest(&pubkey.serialize_uncompressed()[1..]);
+                Address::from_bytes(address_hash.as_bytes()[12..].to_vec())
+            })
+            .collect();
+
+        let dest_db = MemoryDB::new();
+        let chunk_source = DbChunkSource { db: &snapshot_db };
+        restore_snapshot(
+            &DbTrie { db: &dest_db },
+            &dest_db,
+            &manifest,
+            &proof,
+            &verifier_list,
+            &chunk_source,
+        )
+        .unwrap();
+
+        assert!(dest_db.contains(DataCategory::State, root.as_bytes()).wait().unwrap());
+        assert!(dest_db.contains(DataCategory::State, leaf_a.as_bytes()).wait().unwrap());
+        assert!(dest_db.contains(DataCategory::State, leaf_b.as_bytes()).wait().unwrap());
+    }
+
+    #[test]
+    fn restore_snapshot_rejects_proof_that_fails_quorum() {
+        let db = MemoryDB::new();
+        let header = BlockHeader::default();
+        let manifest = SnapshotManifest {
+            block_header: header.clone(),
+            state_root:   Hash::digest(b"root"),
+            chunk_hashes: Vec::new(),
+        };
+        let proof = Proof {
+            block_hash: header.hash(),
+            ..Default::default()
+        };
+        let source = StaticChunkSource {
+            chunks: std::collections::HashMap::new(),
+        };
+
+        struct EmptyTrie;
+        impl TrieReader for EmptyTrie {
+            fn read_node(&self, _hash: &Hash) -> Result<(Vec<u8>, Vec<Hash>), SnapshotError> {
+                unreachable!("quorum check must fail before any trie access")
+            }
+            fn has_node(&self, _hash: &Hash) -> Result<bool, SnapshotError> {
+                unreachable!("quorum check must fail before any trie access")
+            }
+        }
+
+        // An empty verifier_list can never reach quorum, regardless of `proof.commits`.
+        let err = restore_snapshot(&EmptyTrie, &db, &manifest, &proof, &[], &source).unwrap_err();
+        assert!(matches!(err, SnapshotError::UnauthenticatedHeader));
+    }
+}
@@ -0,0 +1,30 @@
+use std::default::Default;
+
+use hasher::{Hasher, HasherKeccak};
+use serde_derive::{Deserialize, Serialize};
+
+/// A 32-byte keccak256 digest, used for block hashes, state roots and trie
+/// node keys.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Hash(Vec<u8>);
+
+impl Hash {
+    pub fn digest(data: &[u8]) -> Self {
+        Hash(HasherKeccak::new().digest(data))
+    }
+
+    pub fn from_hex(s: &str) -> Result<Self, String> {
+        let s = s.trim_start_matches("0x");
+        hex::decode(s)
+            .map(Hash)
+            .map_err(|e| format!("invalid hash hex: {}", e))
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Hash(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
@@ -0,0 +1,162 @@
+use std::default::Default;
+
+use serde_derive::{Deserialize, Serialize};
+
+mod hash;
+
+pub use crate::hash::Hash;
+
+/// A 20-byte account address.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct Address(Vec<u8>);
+
+impl Address {
+    pub fn from_hex(s: &str) -> Result<Self, String> {
+        let s = s.trim_start_matches("0x");
+        hex::decode(s)
+            .map(Address)
+            .map_err(|e| format!("invalid address hex: {}", e))
+    }
+
+    pub fn from_bytes(bytes: Vec<u8>) -> Self {
+        Address(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        &self.0
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BlockHeader {
+    pub prevhash:    Hash,
+    pub timestamp:   u64,
+    pub height:      u64,
+    pub state_root:  Hash,
+    pub transactions_root: Hash,
+    pub receipts_root: Hash,
+    pub gas_used:    u64,
+    pub quota_limit: u64,
+    pub proposer:    Address,
+    /// EIP-1559 base fee paid by every transaction in this block, in the
+    /// smallest fee unit. Computed from the parent header by
+    /// `next_base_fee_per_gas` and carried forward block to block.
+    pub base_fee_per_gas: u64,
+}
+
+impl BlockHeader {
+    pub fn hash(&self) -> Hash {
+        Hash::digest(&serde_json::to_vec(self).expect("BlockHeader must serialize"))
+    }
+
+    /// Computes the base fee the *next* block must pay, following this
+    /// header as the parent. The target is half of `quota_limit`; the fee
+    /// moves by at most 1/8 per block depending on how far `gas_used`
+    /// deviates from that target, and never drops below `floor`.
+    pub fn next_base_fee_per_gas(&self, floor: u64) -> u64 {
+        let gas_target = self.quota_limit / 2;
+        if gas_target == 0 {
+            return self.base_fee_per_gas.max(floor);
+        }
+
+        let base_fee = i128::from(self.base_fee_per_gas);
+        let delta = i128::from(self.gas_used) - i128::from(gas_target);
+        let change = base_fee * delta / i128::from(gas_target) / 8;
+        let next = base_fee + change;
+
+        next.max(i128::from(floor)) as u64
+    }
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Block {
+    pub hash:   Hash,
+    pub header: BlockHeader,
+    /// Per-transaction fee data needed to answer `eth_feeHistory` reward
+    /// percentiles without re-executing the block.
+    pub tx_fee_samples: Vec<TxFeeSample>,
+}
+
+/// The fee-relevant facts about one transaction in a block: how much
+/// priority fee it actually paid and how much gas it consumed, used to
+/// weight reward-percentile queries.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TxFeeSample {
+    pub effective_priority_fee: u64,
+    pub gas_used:               u64,
+}
+
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Proof {
+    pub height:    u64,
+    pub round:     u64,
+    pub block_hash: Hash,
+    /// One entry per verifier who precommitted `block_hash`, each
+    /// independently verifiable against `verifier_list` by
+    /// `core_consensus::verify_proof`.
+    pub commits:   Vec<Commit>,
+}
+
+/// A single verifier's signed precommit vote for a `Proof`'s `block_hash`.
+#[derive(Default, Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Commit {
+    pub address:   Address,
+    pub signature: Vec<u8>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct Genesis {
+    pub prevhash:  String,
+    pub timestamp: u64,
+    pub state_alloc: Vec<GenesisAlloc>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GenesisAlloc {
+    pub address: String,
+    pub balance: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn header(gas_used: u64, quota_limit: u64, base_fee_per_gas: u64) -> BlockHeader {
+        BlockHeader {
+            gas_used,
+            quota_limit,
+            base_fee_per_gas,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn next_base_fee_holds_steady_at_target() {
+        let parent = header(500, 1000, 100);
+        assert_eq!(parent.next_base_fee_per_gas(1), 100);
+    }
+
+    #[test]
+    fn next_base_fee_rises_above_target() {
+        let parent = header(1000, 1000, 100);
+        assert_eq!(parent.next_base_fee_per_gas(1), 112);
+    }
+
+    #[test]
+    fn next_base_fee_falls_below_target() {
+        let parent = header(0, 1000, 100);
+        assert_eq!(parent.next_base_fee_per_gas(1), 88);
+    }
+
+    #[test]
+    fn next_base_fee_never_drops_below_floor() {
+        let parent = header(0, 1000, 1);
+        assert_eq!(parent.next_base_fee_per_gas(10), 10);
+    }
+
+    #[test]
+    fn next_base_fee_with_zero_quota_limit_holds_at_floor() {
+        let parent = header(0, 0, 5);
+        assert_eq!(parent.next_base_fee_per_gas(10), 10);
+    }
+}
@@ -11,6 +11,7 @@ use futures01::future::Future as Future01;
 use futures01::sync::mpsc::channel;
 use serde_derive::Deserialize;
 
+use components_database::cached::{CacheConfig, CachedDatabase};
 use components_database::rocks::RocksDB;
 use components_executor::evm::{EVMBlockDataProvider, EVMExecutor};
 use components_executor::TrieDB;
@@ -25,6 +26,7 @@ use core_crypto::{
 use core_network::reactor::{outbound, CallbackMap, ChainReactor, InboundReactor, OutboundReactor};
 use core_network::Network;
 use core_pubsub::PubSub;
+use core_storage::pruning::PruningJournal;
 use core_storage::{BlockStorage, Storage};
 use core_types::{Address, Block, BlockHeader, Genesis, Hash, Proof};
 use logger;
@@ -38,8 +40,42 @@ struct Config {
     rpc_address: String,
     rpc_workers: u64,
 
+    // "full" runs the executor and stores state; "light" only syncs and
+    // verifies headers + BFT proofs, answering state queries over the
+    // network with Merkle proofs instead.
+    #[serde(default = "default_mode")]
+    mode: String,
+
     // db config
     data_path: PathBuf,
+    #[serde(default)]
+    fat_db:    bool,
+
+    // if set, a brand-new node tries to restore state from a peer-served
+    // snapshot instead of re-executing the chain from genesis
+    #[serde(default)]
+    fast_sync: bool,
+
+    // per-category LRU cache capacities in front of block_db/state_db
+    #[serde(default = "default_cache_size_block")]
+    cache_size_block: usize,
+    #[serde(default = "default_cache_size_state")]
+    cache_size_state: usize,
+
+    // pruning: "archive" keeps every historical trie node; "fast" selects
+    // PruningJournal's refcounted mode with a `pruning_history` window, but
+    // nothing in `start` calls commit_block/prune_at, so every node is
+    // retained as under "archive" (start_full logs a warning saying so).
+    #[serde(default = "default_pruning")]
+    pruning: String,
+    #[serde(default = "default_pruning_history")]
+    pruning_history: u64,
+
+    // floor for `base_fee_per_gas`: `BlockHeader::next_base_fee_per_gas`
+    // never goes below it, and eth_feeHistory reports it for heights with
+    // no stored block; see `components_jsonrpc::Config::base_fee_floor`
+    #[serde(default = "default_base_fee_floor")]
+    base_fee_floor: u64,
 
     // transaction pool
     pool_size:         u64,
@@ -53,7 +89,35 @@ struct Config {
     consensus_wal_path:      String,
 }
 
+fn default_mode() -> String {
+    "full".to_owned()
+}
+
+fn default_cache_size_block() -> usize {
+    CacheConfig::default().block
+}
+
+fn default_cache_size_state() -> usize {
+    CacheConfig::default().state
+}
+
+fn default_pruning() -> String {
+    "archive".to_owned()
+}
+
+fn default_pruning_history() -> u64 {
+    0
+}
+
+fn default_base_fee_floor() -> u64 {
+    components_jsonrpc::Config::default().base_fee_floor
+}
+
 impl Config {
+    pub fn is_light(&self) -> bool {
+        self.mode == "light"
+    }
+
     pub fn data_path_for_state(&self) -> PathBuf {
         let mut path_state = self.data_path.clone();
         path_state.push("state_data");
@@ -65,6 +129,16 @@ impl Config {
         path_state.push("block_data");
         path_state
     }
+
+    pub fn pruning_mode(&self) -> core_storage::pruning::PruningMode {
+        match self.pruning.as_str() {
+            "archive" => core_storage::pruning::PruningMode::Archive,
+            "fast" => core_storage::pruning::PruningMode::Fast {
+                history: self.pruning_history,
+            },
+            other => panic!("unknown pruning mode: {}", other),
+        }
+    }
 }
 
 fn main() {
@@ -101,19 +175,122 @@ fn main() {
 }
 
 fn start(cfg: &Config) {
+    if cfg.is_light() {
+        start_light(cfg);
+    } else {
+        start_full(cfg);
+    }
+}
+
+/// Verifies the BFT proof of the latest locally stored header against
+/// `consensus_verifier_list` and serves state queries against that header;
+/// never constructs a `TrieDB`/`EVMExecutor`, so it never stores full
+/// state. It does not fetch new headers from peers, and state queries go
+/// through `NetworkProofSource`, which returns an error for every proof
+/// request.
+fn start_light(cfg: &Config) {
+    let ctx = Context::new();
+
+    let block_db = Arc::new(RocksDB::new(cfg.data_path_for_block().to_str().unwrap()).unwrap());
+    let storage = Arc::new(BlockStorage::new(block_db));
+
+    let block = storage.get_latest_block(ctx.clone()).wait().unwrap();
+    let proof = storage.get_latest_proof(ctx.clone()).wait().unwrap();
+
+    let mut verifier_list = Vec::with_capacity(cfg.consensus_verifier_list.len());
+    for address in cfg.consensus_verifier_list.iter() {
+        verifier_list.push(Address::from_hex(address).unwrap());
+    }
+
+    if !core_consensus::verify_proof(&proof, &verifier_list) {
+        log::error!("Trusted header's proof failed verification, refusing to start in light mode");
+        return;
+    }
+
+    let mut jrpc_config = components_jsonrpc::Config::default();
+    jrpc_config.listen = cfg.rpc_address.clone();
+    jrpc_config.workers = if cfg.rpc_workers != 0 {
+        cfg.rpc_workers as usize
+    } else {
+        cmp::min(2, num_cpus::get())
+    };
+    let jrpc_state = components_jsonrpc::LightAppState::new(
+        Arc::new(core_network::proof::NetworkProofSource::new()),
+        block.header.clone(),
+    );
+
+    if let Err(e) = components_jsonrpc::listen_light(jrpc_config, jrpc_state) {
+        log::error!("Failed to start jrpc server: {}", e);
+    };
+}
+
+/// Attempts to fast-sync `trie_db`'s state from a peer-served snapshot
+/// instead of re-executing the chain from genesis. The fetched manifest's
+/// header is only trusted once its accompanying proof verifies against
+/// `consensus_verifier_list` and actually attests to that header. A failed
+/// manifest fetch or an unauthenticated manifest just logs and leaves
+/// `trie_db` untouched; a restore that fails partway logs and keeps the
+/// chunks it already imported. Either way the node then falls back to
+/// building state up from genesis as before.
+fn try_fast_sync<DB: core_runtime::Database>(cfg: &Config, trie_db: &TrieDB<DB>, db: &DB) {
+    let (manifest, proof) = match core_network::snapshot::fetch_manifest() {
+        Ok(result) => result,
+        Err(e) => {
+            log::error!("Fast sync: couldn't fetch a snapshot manifest: {:?}", e);
+            return;
+        }
+    };
+
+    let mut verifier_list = Vec::with_capacity(cfg.consensus_verifier_list.len());
+    for address in cfg.consensus_verifier_list.iter() {
+        verifier_list.push(Address::from_hex(address).unwrap());
+    }
+
+    let source = core_network::snapshot::NetworkChunkSource::new();
+    if let Err(e) = core_storage::snapshot::restore_snapshot(trie_db, db, &manifest, &proof, &verifier_list, &source) {
+        log::error!("Fast sync failed, falling back to syncing from genesis: {:?}", e);
+    }
+}
+
+fn start_full(cfg: &Config) {
     // new context
     let ctx = Context::new();
 
     // new crypto
     let secp = Arc::new(Secp256k1::new());
 
-    // new db
-    let block_db = Arc::new(RocksDB::new(cfg.data_path_for_block().to_str().unwrap()).unwrap());
-    let state_db = Arc::new(RocksDB::new(cfg.data_path_for_state().to_str().unwrap()).unwrap());
+    // new db, wrapped in a per-category LRU read cache since consensus and
+    // RPC both repeatedly re-read the same latest block/proof and trie nodes
+    let block_db = Arc::new(CachedDatabase::new(
+        RocksDB::new(cfg.data_path_for_block().to_str().unwrap()).unwrap(),
+        CacheConfig {
+            block: cfg.cache_size_block,
+            ..CacheConfig::default()
+        },
+    ));
+    let state_db = Arc::new(CachedDatabase::new(
+        RocksDB::new(cfg.data_path_for_state().to_str().unwrap()).unwrap(),
+        CacheConfig {
+            state: cfg.cache_size_state,
+            ..CacheConfig::default()
+        },
+    ));
 
     // new storage and trie db
     let storage = Arc::new(BlockStorage::new(Arc::clone(&block_db)));
-    let trie_db = TrieDB::new(Arc::clone(&state_db));
+    let trie_db = TrieDB::with_fat_db(Arc::clone(&state_db), cfg.fat_db);
+    if let core_storage::pruning::PruningMode::Fast { .. } = cfg.pruning_mode() {
+        log::warn!(
+            "pruning = \"fast\" is configured, but no block-commit or reorg path calls \
+             PruningJournal::commit_block/prune_at/rollback_block yet: this is currently a no-op \
+             and every historical trie node is retained, same as pruning = \"archive\""
+        );
+    }
+    let pruning_journal = Arc::new(PruningJournal::new(Arc::clone(&state_db), cfg.pruning_mode()));
+
+    if cfg.fast_sync {
+        try_fast_sync(cfg, &trie_db, &state_db);
+    }
 
     // new executor
     let block = storage.get_latest_block(ctx.clone()).wait().unwrap();
@@ -121,6 +298,7 @@ fn start(cfg: &Config) {
         EVMExecutor::from_existing(
             trie_db,
             Arc::new(EVMBlockDataProvider::new(Arc::clone(&storage))),
+            Arc::clone(&pruning_journal),
             &block.header.state_root,
         )
         .unwrap(),
@@ -161,10 +339,13 @@ fn start(cfg: &Config) {
     } else {
         cmp::min(2, num_cpus::get())
     };
+    jrpc_config.base_fee_floor = cfg.base_fee_floor;
     let jrpc_state = components_jsonrpc::AppState::new(
         Arc::clone(&executor),
+        Arc::clone(&state_db),
         Arc::clone(&tx_pool),
         Arc::clone(&storage),
+        jrpc_config.base_fee_floor,
     );
 
     // new consensus
@@ -232,12 +413,14 @@ fn handle_init(cfg: &Config, genesis_path: impl AsRef<Path>) -> Result<(), Box<d
     let path_state = cfg.data_path_for_state();
     log::info!("Data path for state: {:?}", path_state);
     let state_disk_db = Arc::new(RocksDB::new(path_state.to_str().unwrap())?);
-    let state_db = TrieDB::new(state_disk_db);
+    let pruning_journal = Arc::new(PruningJournal::new(Arc::clone(&state_disk_db), cfg.pruning_mode()));
+    let state_db = TrieDB::with_fat_db(state_disk_db, cfg.fat_db);
 
     let (_, state_root_hash) = EVMExecutor::from_genesis(
         &genesis,
         state_db,
         Arc::new(EVMBlockDataProvider::new(Arc::clone(&block_db))),
+        pruning_journal,
     )?;
     log::info!("State root hash: {:?}", state_root_hash);
 